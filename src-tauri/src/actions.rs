@@ -0,0 +1,451 @@
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::api_host::{resolve_api_root, resolve_endpoints_base_url};
+use crate::client::SophosClient;
+use crate::{
+    describe_api_error, edit_cached_endpoints, fetch_with_saved_credentials, http_client, load_cached_endpoints,
+    require_credentials, validate_region, SophosError,
+};
+
+/// Upper bound on simultaneous requests in bulk actions (deletes, scans, isolation).
+pub(crate) const BULK_ACTION_CONCURRENCY: usize = 3;
+
+/// Actions Sophos accepts on `/common/v1/alerts/{id}/actions`.
+const ALERT_ACTIONS: &[&str] = &[
+    "acknowledge",
+    "cleanPua",
+    "cleanVirus",
+    "authPua",
+    "clearThreat",
+    "clearHmpa",
+];
+
+/// Accepts alert ids made of letters, digits and hyphens (Sophos uses UUIDs), so the id
+/// can't add path segments or a query to the action URL.
+fn validate_alert_id(alert_id: &str) -> Result<(), String> {
+    if alert_id.is_empty() {
+        return Err("Alert id is empty".to_string());
+    }
+    if !alert_id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+        return Err(format!("Alert id '{}' is not a valid alert id", alert_id));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn perform_alert_action(
+    access_token: String,
+    alert_id: String,
+    action: String,
+) -> Result<serde_json::Value, String> {
+    if !ALERT_ACTIONS.contains(&action.as_str()) {
+        return Err(format!(
+            "Unsupported alert action '{}'. Allowed actions: {}",
+            action,
+            ALERT_ACTIONS.join(", ")
+        ));
+    }
+
+    let alert_id = alert_id.trim();
+    validate_alert_id(alert_id)?;
+
+    let credentials = require_credentials()?;
+    let client = http_client()?;
+    let api_root = resolve_api_root(
+        &client,
+        &access_token,
+        &credentials.tenant_id,
+        &credentials.region,
+        credentials.allow_unknown_region,
+    )
+    .await?;
+
+    send_alert_action(&client, &api_root, &access_token, &credentials.tenant_id, alert_id, &action).await
+}
+
+/// Posts `action` for one alert under `api_root`. A missing alert is reported as
+/// `alertNotFound` rather than an error.
+async fn send_alert_action(
+    client: &impl SophosClient,
+    api_root: &str,
+    access_token: &str,
+    tenant_id: &str,
+    alert_id: &str,
+    action: &str,
+) -> Result<serde_json::Value, String> {
+    let url = format!("{}/common/v1/alerts/{}/actions", api_root, alert_id);
+    let response = client
+        .post_action(&url, access_token, tenant_id, &serde_json::json!({ "action": action }))
+        .await
+        .map_err(|e| format!("Request failed: {}", e))?;
+
+    // The alert was already resolved or removed; report it so the UI can drop it
+    if response.status == 404 {
+        println!("⚠️  Alert {} no longer exists", alert_id);
+        return Ok(serde_json::json!({
+            "id": alert_id,
+            "action": action,
+            "result": "alertNotFound",
+        }));
+    }
+
+    if !(200..300).contains(&response.status) {
+        let detail = describe_api_error(&response.body);
+        return Err(format!("Alert action '{}' failed ({}): {}", action, response.status, detail));
+    }
+
+    let result: serde_json::Value =
+        serde_json::from_str(&response.body).map_err(|e| format!("Failed to parse response: {}", e))?;
+
+    println!("✅ Alert {} action '{}' submitted", alert_id, action);
+    Ok(result)
+}
+
+async fn delete_endpoint_request(
+    client: &impl SophosClient,
+    base_url: &str,
+    access_token: &str,
+    tenant_id: &str,
+    endpoint_id: &str,
+) -> Result<(), SophosError> {
+    let response = client
+        .delete_resource(&format!("{}/{}", base_url, endpoint_id), access_token, tenant_id)
+        .await
+        .map_err(|e| SophosError::Request(format!("Delete request failed for {}: {}", endpoint_id, e)))?;
+
+    if !(200..300).contains(&response.status) {
+        return Err(SophosError::Api {
+            status: response.status,
+            message: format!(
+                "Delete failed for {} ({}): {}",
+                endpoint_id,
+                response.status,
+                describe_api_error(&response.body)
+            ),
+        });
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct DeleteOutcome {
+    id: String,
+    hostname: Option<String>,
+    success: bool,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct BulkDeleteResult {
+    /// True when `confirm` was false and nothing was deleted
+    dry_run: bool,
+    candidates: usize,
+    deleted: usize,
+    failed: usize,
+    results: Vec<DeleteOutcome>,
+}
+
+/// Smallest `days` accepted by `delete_stale_endpoints`; 0 would make every endpoint with
+/// a `lastSeen` a candidate.
+const MIN_STALE_DAYS: u64 = 1;
+
+/// The `lastSeen` cutoff for endpoints not seen in `days` days, or an error when `days`
+/// is below `MIN_STALE_DAYS` or too large to subtract from `now`.
+fn stale_cutoff(days: u64, now: chrono::DateTime<chrono::Utc>) -> Result<chrono::DateTime<chrono::Utc>, String> {
+    if days < MIN_STALE_DAYS {
+        return Err(format!("days must be at least {}", MIN_STALE_DAYS));
+    }
+    let out_of_range = || format!("days value {} is out of range", days);
+    let age = i64::try_from(days)
+        .ok()
+        .and_then(chrono::Duration::try_days)
+        .ok_or_else(out_of_range)?;
+    now.checked_sub_signed(age).ok_or_else(out_of_range)
+}
+
+/// Deletes endpoints not seen for `days` days. Without `confirm: true` this only reports
+/// what would be deleted. Endpoints with no `lastSeen` are never candidates, since their
+/// age is unknown.
+#[tauri::command]
+pub(crate) async fn delete_stale_endpoints(
+    access_token: String,
+    days: u64,
+    confirm: bool,
+) -> Result<BulkDeleteResult, String> {
+    let cutoff = stale_cutoff(days, chrono::Utc::now())?;
+    let credentials = require_credentials()?;
+    let endpoints = fetch_with_saved_credentials(access_token.clone()).await?;
+
+    let stale: Vec<_> = endpoints
+        .into_iter()
+        .filter(|endpoint| endpoint.last_seen_at().is_some_and(|last_seen| last_seen < cutoff))
+        .collect();
+
+    println!("🧹 {} endpoints not seen in {} days", stale.len(), days);
+
+    if !confirm {
+        let results = stale
+            .into_iter()
+            .map(|endpoint| DeleteOutcome {
+                id: endpoint.id,
+                hostname: endpoint.hostname,
+                success: false,
+                error: None,
+            })
+            .collect::<Vec<_>>();
+        return Ok(BulkDeleteResult {
+            dry_run: true,
+            candidates: results.len(),
+            deleted: 0,
+            failed: 0,
+            results,
+        });
+    }
+
+    let client = http_client()?;
+    let base_url = resolve_endpoints_base_url(&client, &access_token, &credentials).await?;
+    let candidates = stale.len();
+
+    let results: Vec<DeleteOutcome> = stream::iter(stale)
+        .map(|endpoint| {
+            let client = client.clone();
+            let base_url = base_url.clone();
+            let access_token = access_token.clone();
+            let tenant_id = credentials.tenant_id.clone();
+            async move {
+                let result =
+                    delete_endpoint_request(&client, &base_url, &access_token, &tenant_id, &endpoint.id).await;
+                DeleteOutcome {
+                    id: endpoint.id,
+                    hostname: endpoint.hostname,
+                    success: result.is_ok(),
+                    error: result.err().map(|e| e.to_string()),
+                }
+            }
+        })
+        .buffer_unordered(BULK_ACTION_CONCURRENCY)
+        .collect()
+        .await;
+
+    let deleted_ids: Vec<&str> = results
+        .iter()
+        .filter(|outcome| outcome.success)
+        .map(|outcome| outcome.id.as_str())
+        .collect();
+    if !deleted_ids.is_empty() {
+        if let Err(e) = edit_cached_endpoints(|cached| cached.retain(|endpoint| !deleted_ids.contains(&endpoint.id.as_str()))) {
+            println!("⚠️  Could not update cache after delete: {}", e);
+        }
+    }
+
+    let deleted = deleted_ids.len();
+    println!("🗑️  Deleted {} of {} stale endpoints", deleted, candidates);
+
+    Ok(BulkDeleteResult {
+        dry_run: false,
+        candidates,
+        deleted,
+        failed: candidates - deleted,
+        results,
+    })
+}
+
+#[derive(Debug, Deserialize)]
+struct ScanResponse {
+    id: Option<String>,
+}
+
+/// Requests an on-demand scan and returns the job id Sophos assigns to it, if any.
+async fn scan_endpoint_request(
+    client: &impl SophosClient,
+    base_url: &str,
+    access_token: &str,
+    tenant_id: &str,
+    endpoint_id: &str,
+) -> Result<Option<String>, SophosError> {
+    let response = client
+        .post_action(
+            &format!("{}/{}/scans", base_url, endpoint_id),
+            access_token,
+            tenant_id,
+            &serde_json::json!({}),
+        )
+        .await
+        .map_err(|e| SophosError::Request(format!("Scan request failed for {}: {}", endpoint_id, e)))?;
+
+    if !(200..300).contains(&response.status) {
+        return Err(SophosError::Api {
+            status: response.status,
+            message: format!(
+                "Scan failed for {} ({}): {}",
+                endpoint_id,
+                response.status,
+                describe_api_error(&response.body)
+            ),
+        });
+    }
+
+    // An unparseable body still means the scan was accepted
+    let scan: Option<ScanResponse> = serde_json::from_str(&response.body).ok();
+    Ok(scan.and_then(|scan| scan.id))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ScanOutcome {
+    id: String,
+    hostname: Option<String>,
+    job_id: Option<String>,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct BulkScanResult {
+    requested: usize,
+    queued: usize,
+    failed: usize,
+    /// Offline endpoints left out because of `online_only`
+    skipped_offline: usize,
+    results: Vec<ScanOutcome>,
+}
+
+/// Starts a scan on every cached endpoint, or only the online ones with `online_only`.
+/// Requests go through the shared rate limiter, at most `BULK_ACTION_CONCURRENCY` at a time.
+#[tauri::command]
+pub(crate) async fn scan_all_endpoints(access_token: String, online_only: bool) -> Result<BulkScanResult, String> {
+    let credentials = require_credentials()?;
+    validate_region(&credentials.region, credentials.allow_unknown_region)?;
+    let endpoints = load_cached_endpoints()?;
+
+    let total = endpoints.len();
+    let targets: Vec<_> = endpoints
+        .into_iter()
+        .filter(|endpoint| !online_only || endpoint.online == Some(true))
+        .collect();
+    let skipped_offline = total - targets.len();
+    let requested = targets.len();
+
+    println!("🔍 Requesting scans on {} endpoints ({} offline skipped)", requested, skipped_offline);
+
+    let client = http_client()?;
+    let base_url = resolve_endpoints_base_url(&client, &access_token, &credentials).await?;
+
+    let results: Vec<ScanOutcome> = stream::iter(targets)
+        .map(|endpoint| {
+            let client = client.clone();
+            let base_url = base_url.clone();
+            let access_token = access_token.clone();
+            let tenant_id = credentials.tenant_id.clone();
+            async move {
+                let result =
+                    scan_endpoint_request(&client, &base_url, &access_token, &tenant_id, &endpoint.id).await;
+                match result {
+                    Ok(job_id) => ScanOutcome {
+                        id: endpoint.id,
+                        hostname: endpoint.hostname,
+                        job_id,
+                        error: None,
+                    },
+                    Err(e) => ScanOutcome {
+                        id: endpoint.id,
+                        hostname: endpoint.hostname,
+                        job_id: None,
+                        error: Some(e.to_string()),
+                    },
+                }
+            }
+        })
+        .buffer_unordered(BULK_ACTION_CONCURRENCY)
+        .collect()
+        .await;
+
+    let failed = results.iter().filter(|outcome| outcome.error.is_some()).count();
+    let queued = requested - failed;
+    println!("✅ Queued {} scans, {} failed", queued, failed);
+
+    Ok(BulkScanResult {
+        requested,
+        queued,
+        failed,
+        skipped_offline,
+        results,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{json_response, status, FakeClient};
+
+    const API_ROOT: &str = "https://api-eu01.central.sophos.com";
+
+    #[test]
+    fn stale_cutoff_rejects_zero_days() {
+        assert!(stale_cutoff(0, chrono::Utc::now()).is_err());
+    }
+
+    #[test]
+    fn stale_cutoff_rejects_days_out_of_range() {
+        let now = chrono::Utc::now();
+        assert!(stale_cutoff(u64::MAX, now).is_err());
+        assert!(stale_cutoff(i64::MAX as u64, now).is_err());
+        assert!(stale_cutoff(1_000_000_000, now).is_err());
+    }
+
+    #[test]
+    fn stale_cutoff_is_in_the_past() {
+        let now = chrono::Utc::now();
+        assert_eq!(stale_cutoff(1, now).unwrap(), now - chrono::Duration::days(1));
+        assert_eq!(stale_cutoff(90, now).unwrap(), now - chrono::Duration::days(90));
+    }
+
+    #[test]
+    fn alert_id_rejects_path_and_query_characters() {
+        assert!(validate_alert_id("3b8a1f2e-5c4d-4e6f-9a0b-1c2d3e4f5a6b").is_ok());
+        for alert_id in ["", "../endpoint/v1/endpoints", "a/b", "a?x=1", "a#b", "a b", "%2e%2e"] {
+            assert!(validate_alert_id(alert_id).is_err(), "{:?}", alert_id);
+        }
+    }
+
+    #[tokio::test]
+    async fn alert_action_posts_the_action() {
+        let client = FakeClient::with_responses(vec![json_response(serde_json::json!({ "id": "job" }))]);
+
+        let result = send_alert_action(&client, API_ROOT, "token", "tenant", "alert-1", "acknowledge")
+            .await
+            .unwrap();
+
+        assert_eq!(result["id"], "job");
+        let call = &client.calls()[0];
+        assert_eq!(call.method, "POST");
+        assert_eq!(call.url, format!("{}/common/v1/alerts/alert-1/actions", API_ROOT));
+        assert_eq!(call.body, Some(serde_json::json!({ "action": "acknowledge" })));
+    }
+
+    #[tokio::test]
+    async fn missing_alert_is_reported_not_failed() {
+        let client = FakeClient::with_responses(vec![status(404), status(500)]);
+
+        let result = send_alert_action(&client, API_ROOT, "token", "tenant", "gone", "acknowledge")
+            .await
+            .unwrap();
+        assert_eq!(result["result"], "alertNotFound");
+
+        let failed = send_alert_action(&client, API_ROOT, "token", "tenant", "alert-1", "acknowledge").await;
+        assert!(failed.unwrap_err().contains("(500)"));
+    }
+
+    #[tokio::test]
+    async fn delete_sends_delete_and_keeps_the_api_status() {
+        let base_url = format!("{}/endpoint/v1/endpoints", API_ROOT);
+        let client = FakeClient::with_responses(vec![json_response(serde_json::json!({})), status(403)]);
+
+        delete_endpoint_request(&client, &base_url, "token", "tenant", "a").await.unwrap();
+        let denied = delete_endpoint_request(&client, &base_url, "token", "tenant", "b").await;
+
+        assert!(matches!(denied, Err(SophosError::Api { status: 403, .. })));
+        let call = &client.calls()[0];
+        assert_eq!((call.method, call.url.as_str()), ("DELETE", format!("{}/a", base_url).as_str()));
+    }
+}
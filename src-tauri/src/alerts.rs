@@ -0,0 +1,101 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tauri::Emitter;
+
+use crate::snapshots::{diff_inventories, previous_snapshot_path};
+use crate::{app_handle, cache_file, read_cache_file, SophosEndpoint};
+
+const ENDPOINT_ALERT_EVENT: &str = "sophos-endpoint-alert";
+
+/// A flapping endpoint is reported at most once per kind within this window.
+const ALERT_DEBOUNCE: Duration = Duration::from_secs(15 * 60);
+
+static LAST_ALERTED: Mutex<BTreeMap<(String, AlertKind), Instant>> = Mutex::new(BTreeMap::new());
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum AlertKind {
+    WentOffline,
+    HealthDegraded,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct EndpointAlert {
+    kind: AlertKind,
+    endpoint: SophosEndpoint,
+}
+
+/// True if this alert wasn't already raised within the debounce window, and records it.
+fn should_alert(endpoint_id: &str, kind: AlertKind) -> bool {
+    let Ok(mut last_alerted) = LAST_ALERTED.lock() else {
+        return true;
+    };
+
+    let now = Instant::now();
+    last_alerted.retain(|_, at| now.duration_since(*at) < ALERT_DEBOUNCE);
+
+    let key = (endpoint_id.to_string(), kind);
+    if last_alerted.contains_key(&key) {
+        return false;
+    }
+    last_alerted.insert(key, now);
+    true
+}
+
+/// Compares a freshly written cache with its previous snapshot and emits
+/// `sophos-endpoint-alert` for endpoints that went online→offline or good→bad health.
+pub(crate) fn emit_refresh_alerts(cache_path: &Path) {
+    let Some(app) = app_handle() else {
+        return;
+    };
+
+    let previous_path = previous_snapshot_path(cache_path);
+    if !cache_file::exists(&previous_path) {
+        return;
+    }
+    let (Ok(previous), Ok(current)) = (read_cache_file(&previous_path), read_cache_file(cache_path)) else {
+        return;
+    };
+    if previous.tenant_id != current.tenant_id {
+        return;
+    }
+
+    let current_by_id: BTreeMap<&str, &SophosEndpoint> = current
+        .endpoints
+        .iter()
+        .map(|endpoint| (endpoint.id.as_str(), endpoint))
+        .collect();
+    let diff = diff_inventories(previous.endpoints.clone(), current.endpoints.clone());
+
+    let went_offline = diff
+        .online_changed
+        .iter()
+        .filter(|(_, was_online, is_online)| *was_online && !*is_online)
+        .map(|(id, _, _)| (id, AlertKind::WentOffline));
+    let degraded = diff
+        .health_changed
+        .iter()
+        .filter(|(_, old, new)| old == "good" && new == "bad")
+        .map(|(id, _, _)| (id, AlertKind::HealthDegraded));
+
+    let alerts: Vec<EndpointAlert> = went_offline
+        .chain(degraded)
+        .filter(|(id, kind)| should_alert(id, *kind))
+        .filter_map(|(id, kind)| {
+            current_by_id.get(id.as_str()).map(|endpoint| EndpointAlert {
+                kind,
+                endpoint: (*endpoint).clone(),
+            })
+        })
+        .collect();
+
+    if alerts.is_empty() {
+        return;
+    }
+
+    println!("🚨 {} endpoint alerts since last refresh", alerts.len());
+    app.emit(ENDPOINT_ALERT_EVENT, alerts).ok();
+}
@@ -0,0 +1,229 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::client::SophosClient;
+use crate::settings::load_settings;
+use crate::{
+    api_host_url, endpoints_base_url, fetch_whoami, get_app_data_dir, http_client, load_credentials,
+    require_credentials, resolve_api_host, SophosCredentials,
+};
+
+pub(crate) const API_HOST_FILE: &str = "sophos_api_host.json";
+
+/// The data-region host whoami reported for a set of credentials. Keyed by client and
+/// tenant, so changed credentials never reuse another tenant's host.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedApiHost {
+    client_id: String,
+    tenant_id: String,
+    api_host: String,
+    /// Unix timestamp (seconds)
+    resolved_at: u64,
+}
+
+fn get_api_host_path() -> std::path::PathBuf {
+    get_app_data_dir().join(API_HOST_FILE)
+}
+
+/// The whoami host cached for the saved credentials and `tenant_id`, if there is one.
+pub(crate) fn cached_api_host(tenant_id: &str) -> Option<String> {
+    if !load_settings().cache_enabled {
+        return None;
+    }
+
+    let credentials = load_credentials()?;
+    let content = fs::read_to_string(get_api_host_path()).ok()?;
+    let cached: CachedApiHost = match serde_json::from_str(&content) {
+        Ok(cached) => cached,
+        Err(e) => {
+            println!("❌ Failed to parse cached API host: {}", e);
+            return None;
+        }
+    };
+
+    (cached.client_id == credentials.client_id && cached.tenant_id == tenant_id).then_some(cached.api_host)
+}
+
+/// Remembers `api_host` as the data-region host for the saved credentials and `tenant_id`.
+pub(crate) fn store_api_host(tenant_id: &str, api_host: &str) {
+    if !load_settings().cache_enabled {
+        return;
+    }
+    let Some(credentials) = load_credentials() else {
+        return;
+    };
+
+    let cached = CachedApiHost {
+        client_id: credentials.client_id,
+        tenant_id: tenant_id.to_string(),
+        api_host: api_host_url("", Some(api_host)),
+        resolved_at: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs(),
+    };
+    match serde_json::to_string_pretty(&cached) {
+        Ok(json_content) => {
+            if let Err(e) = fs::write(get_api_host_path(), json_content) {
+                println!("❌ Failed to save API host: {}", e);
+            }
+        }
+        Err(e) => println!("❌ Failed to serialize API host: {}", e),
+    }
+}
+
+/// Drops the cached host, e.g. after the region was changed by hand.
+pub(crate) fn forget_api_host() -> Result<(), String> {
+    let path = get_api_host_path();
+    if path.exists() {
+        fs::remove_file(&path).map_err(|e| format!("Failed to remove cached API host: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Asks whoami for the tenant's data-region host and caches it. Fails when whoami
+/// describes a different tenant or reports no data-region host.
+pub(crate) async fn resolve_api_host_via_whoami(
+    client: &impl SophosClient,
+    access_token: &str,
+    tenant_id: &str,
+) -> Result<String, String> {
+    let whoami = fetch_whoami(client, access_token).await?;
+    if whoami.id_type == "tenant" && whoami.id != tenant_id {
+        return Err(format!("Token belongs to tenant {}, not {}", whoami.id, tenant_id));
+    }
+
+    let api_host = whoami
+        .api_hosts
+        .and_then(|hosts| hosts.data_region)
+        .filter(|host| !host.trim().is_empty())
+        .ok_or("Whoami did not report a data-region host")?;
+
+    store_api_host(tenant_id, &api_host);
+    println!("🌍 Resolved data-region host {}", api_host);
+    Ok(api_host_url("", Some(&api_host)))
+}
+
+/// The tenant's data-region host: the cached one, or a fresh whoami lookup when none is
+/// cached. `None` when whoami fails, leaving the caller to fall back to the region.
+pub(crate) async fn data_region_host(client: &impl SophosClient, access_token: &str, tenant_id: &str) -> Option<String> {
+    if let Some(api_host) = cached_api_host(tenant_id) {
+        return Some(api_host);
+    }
+
+    match resolve_api_host_via_whoami(client, access_token, tenant_id).await {
+        Ok(api_host) => Some(api_host),
+        Err(e) => {
+            println!("   ⚠️  Could not resolve data-region host, using the region: {}", e);
+            None
+        }
+    }
+}
+
+/// Root URL of the tenant's API: the whoami data-region host (see `data_region_host`), or
+/// the region's host when whoami can't say. Every tenant API call builds on this, so
+/// fetches, actions and reports all talk to the same host.
+pub(crate) async fn resolve_api_root(
+    client: &impl SophosClient,
+    access_token: &str,
+    tenant_id: &str,
+    region: &str,
+    allow_unknown_region: bool,
+) -> Result<String, String> {
+    let whoami_host = data_region_host(client, access_token, tenant_id).await;
+    resolve_api_host(region, allow_unknown_region, whoami_host.as_deref())
+}
+
+/// The endpoint API base URL for the saved credentials, on the host `resolve_api_root` picks.
+pub(crate) async fn resolve_endpoints_base_url(
+    client: &impl SophosClient,
+    access_token: &str,
+    credentials: &SophosCredentials,
+) -> Result<String, String> {
+    let api_root = resolve_api_root(
+        client,
+        access_token,
+        &credentials.tenant_id,
+        &credentials.region,
+        credentials.allow_unknown_region,
+    )
+    .await?;
+    Ok(endpoints_base_url(&credentials.region, Some(&api_root)))
+}
+
+/// Re-resolves the data-region host through whoami and caches it for later fetches,
+/// replacing whatever was cached.
+#[tauri::command]
+pub(crate) async fn refresh_api_host(access_token: String) -> Result<String, String> {
+    let credentials = require_credentials()?;
+    resolve_api_host_via_whoami(&http_client()?, &access_token, &credentials.tenant_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{isolated_data_dir, json_response, save_credentials, FakeClient};
+
+    fn whoami(tenant_id: &str, data_region: &str) -> Result<crate::client::RawResponse, String> {
+        json_response(serde_json::json!({
+            "id": tenant_id,
+            "idType": "tenant",
+            "apiHosts": { "global": "https://api.central.sophos.com", "dataRegion": data_region },
+        }))
+    }
+
+    #[tokio::test]
+    async fn whoami_host_is_resolved_and_cached() {
+        let _data_dir = isolated_data_dir();
+        save_credentials("client", "tenant");
+        let credentials = require_credentials().unwrap();
+        let client = FakeClient::with_responses(vec![whoami("tenant", "https://api-us03.central.sophos.com")]);
+
+        let base_url = resolve_endpoints_base_url(&client, "token", &credentials).await.unwrap();
+
+        assert_eq!(base_url, "https://api-us03.central.sophos.com/endpoint/v1/endpoints");
+        assert_eq!(client.calls().len(), 1);
+        assert_eq!(client.calls()[0].url, crate::WHOAMI_URL);
+        assert_eq!(cached_api_host("tenant").as_deref(), Some("https://api-us03.central.sophos.com"));
+    }
+
+    #[tokio::test]
+    async fn whoami_for_another_tenant_falls_back_to_the_region() {
+        let _data_dir = isolated_data_dir();
+        save_credentials("client", "tenant");
+        let credentials = require_credentials().unwrap();
+        let client = FakeClient::with_responses(vec![whoami("other-tenant", "https://api-us03.central.sophos.com")]);
+
+        let base_url = resolve_endpoints_base_url(&client, "token", &credentials).await.unwrap();
+
+        assert_eq!(base_url, "https://api-eu01.central.sophos.com/endpoint/v1/endpoints");
+        assert_eq!(cached_api_host("tenant"), None);
+    }
+
+    #[tokio::test]
+    async fn cached_whoami_host_wins_over_the_region() {
+        let _data_dir = isolated_data_dir();
+        save_credentials("client", "tenant");
+        store_api_host("tenant", "api-us03.central.sophos.com");
+        let credentials = require_credentials().unwrap();
+        assert_eq!(credentials.region, "eu01");
+
+        // Served from the cached host, so no whoami request is made
+        let base_url = resolve_endpoints_base_url(&reqwest::Client::new(), "token", &credentials)
+            .await
+            .unwrap();
+
+        assert_eq!(base_url, "https://api-us03.central.sophos.com/endpoint/v1/endpoints");
+    }
+
+    #[test]
+    fn cached_host_is_scoped_to_the_tenant() {
+        let _data_dir = isolated_data_dir();
+        save_credentials("client", "tenant");
+        store_api_host("tenant", "api-us03.central.sophos.com");
+
+        assert_eq!(cached_api_host("tenant").as_deref(), Some("https://api-us03.central.sophos.com"));
+        assert_eq!(cached_api_host("other-tenant"), None);
+    }
+}
@@ -0,0 +1,85 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::time::Duration;
+
+use crate::get_app_data_dir;
+
+pub(crate) const AUDIT_FILE: &str = "fetch_audit.jsonl";
+
+/// Oldest entries are dropped once the log grows past this many lines.
+const MAX_AUDIT_ENTRIES: usize = 5000;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct FetchAuditEntry {
+    timestamp: String,
+    tenant_id: String,
+    endpoint_count: usize,
+    from_cache: bool,
+    duration_ms: u64,
+    app_version: String,
+}
+
+fn get_audit_path() -> std::path::PathBuf {
+    get_app_data_dir().join(AUDIT_FILE)
+}
+
+/// Keeps only the newest `MAX_AUDIT_ENTRIES` lines.
+fn truncate_audit_log(path: &std::path::Path) -> std::io::Result<()> {
+    let content = fs::read_to_string(path)?;
+    let lines: Vec<&str> = content.lines().collect();
+    if lines.len() <= MAX_AUDIT_ENTRIES {
+        return Ok(());
+    }
+
+    let kept = &lines[lines.len() - MAX_AUDIT_ENTRIES..];
+    fs::write(path, format!("{}\n", kept.join("\n")))
+}
+
+/// Appends one line to the fetch audit log. Failures are logged, never returned, so
+/// auditing can't break a fetch.
+pub(crate) fn record_fetch(tenant_id: &str, endpoint_count: usize, from_cache: bool, duration: Duration) {
+    let entry = FetchAuditEntry {
+        timestamp: chrono::Utc::now().to_rfc3339(),
+        tenant_id: tenant_id.to_string(),
+        endpoint_count,
+        from_cache,
+        duration_ms: duration.as_millis() as u64,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+    };
+
+    let line = match serde_json::to_string(&entry) {
+        Ok(line) => line,
+        Err(e) => {
+            println!("❌ Failed to serialize audit entry: {}", e);
+            return;
+        }
+    };
+
+    let path = get_audit_path();
+    let appended = fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+    if let Err(e) = appended.and_then(|_| truncate_audit_log(&path)) {
+        println!("❌ Failed to write fetch audit log: {}", e);
+    }
+}
+
+/// The most recent `limit` fetches, newest first. Lines that don't parse are skipped.
+#[tauri::command]
+pub(crate) async fn get_fetch_history(limit: usize) -> Result<Vec<FetchAuditEntry>, String> {
+    let content = match fs::read_to_string(get_audit_path()) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(format!("Failed to read fetch history: {}", e)),
+    };
+
+    Ok(content
+        .lines()
+        .rev()
+        .filter_map(|line| serde_json::from_str::<FetchAuditEntry>(line).ok())
+        .take(limit)
+        .collect())
+}
@@ -0,0 +1,166 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::{Read, Write};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::settings::SETTINGS_FILE;
+use crate::ui_state::UI_STATE_FILE;
+use crate::views::VIEWS_FILE;
+use crate::{app_data_dir_is_fallback, cache_file, get_app_data_dir, SECRETS_FILE, TENANT_CACHE_PREFIX};
+
+const BACKUP_MANIFEST: &str = "backup_manifest.json";
+const BACKUP_FORMAT_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BackupManifest {
+    format_version: u32,
+    created_at: String,
+    app_version: String,
+    files: Vec<String>,
+}
+
+/// Whether a file in the app data dir belongs in a backup. The token cache is never
+/// bundled; it is short-lived and re-issued on the next request.
+fn is_backup_file(name: &str, include_secrets: bool) -> bool {
+    name == SETTINGS_FILE
+        || name == UI_STATE_FILE
+        || name == VIEWS_FILE
+        || (name.starts_with(TENANT_CACHE_PREFIX) && (name.ends_with(".json") || name.ends_with(".json.gz")))
+        || (include_secrets && name == SECRETS_FILE)
+}
+
+/// Bundles settings, UI state, saved views and every endpoint cache into one zip. The secrets file
+/// is only added when `include_secrets` is set.
+#[tauri::command]
+pub(crate) async fn export_backup(path: String, include_secrets: bool) -> Result<String, String> {
+    let data_dir = get_app_data_dir();
+
+    let mut files = Vec::new();
+    if let Ok(entries) = fs::read_dir(&data_dir) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if entry.path().is_file() && is_backup_file(&name, include_secrets) {
+                files.push(name);
+            }
+        }
+    }
+    files.sort();
+
+    if files.is_empty() {
+        return Err("Nothing to back up: no settings or cache files found".to_string());
+    }
+
+    if include_secrets && files.iter().any(|name| name == SECRETS_FILE) {
+        println!("⚠️  Backup includes the API client secret; store the archive securely");
+    }
+
+    let archive = fs::File::create(&path).map_err(|e| format!("Failed to create backup {}: {}", path, e))?;
+    let mut writer = ZipWriter::new(archive);
+    let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+    let manifest = BackupManifest {
+        format_version: BACKUP_FORMAT_VERSION,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        files: files.clone(),
+    };
+    let manifest_json = serde_json::to_string_pretty(&manifest)
+        .map_err(|e| format!("Failed to serialize backup manifest: {}", e))?;
+    writer
+        .start_file(BACKUP_MANIFEST, options)
+        .map_err(|e| format!("Failed to write backup manifest: {}", e))?;
+    writer
+        .write_all(manifest_json.as_bytes())
+        .map_err(|e| format!("Failed to write backup manifest: {}", e))?;
+
+    for name in &files {
+        let content = fs::read(data_dir.join(name)).map_err(|e| format!("Failed to read {}: {}", name, e))?;
+        writer
+            .start_file(name.as_str(), options)
+            .map_err(|e| format!("Failed to add {} to backup: {}", name, e))?;
+        writer
+            .write_all(&content)
+            .map_err(|e| format!("Failed to add {} to backup: {}", name, e))?;
+    }
+
+    writer.finish().map_err(|e| format!("Failed to finalize backup: {}", e))?;
+
+    println!("📦 Backed up {} files to {}", files.len(), path);
+    Ok(path)
+}
+
+/// Restores a bundle written by `export_backup`. The whole archive is read and checked
+/// against its manifest before anything in the app data dir is touched.
+#[tauri::command]
+pub(crate) async fn import_backup(path: String, overwrite: bool) -> Result<Vec<String>, String> {
+    let archive = fs::File::open(&path).map_err(|e| format!("Failed to open backup {}: {}", path, e))?;
+    let mut archive = ZipArchive::new(archive).map_err(|e| format!("Not a valid backup archive: {}", e))?;
+
+    let manifest: BackupManifest = {
+        let mut entry = archive
+            .by_name(BACKUP_MANIFEST)
+            .map_err(|_| "Not a valid backup archive: manifest is missing".to_string())?;
+        let mut content = String::new();
+        entry
+            .read_to_string(&mut content)
+            .map_err(|e| format!("Failed to read backup manifest: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Invalid backup manifest: {}", e))?
+    };
+
+    if manifest.format_version > BACKUP_FORMAT_VERSION {
+        return Err(format!(
+            "Backup format version {} is newer than this app supports ({})",
+            manifest.format_version, BACKUP_FORMAT_VERSION
+        ));
+    }
+
+    let mut restored = Vec::new();
+    for name in &manifest.files {
+        // Only plain file names we would have written ourselves; anything else could land outside the data dir
+        if name.contains(['/', '\\']) || !is_backup_file(name, true) {
+            return Err(format!("Backup contains an unexpected file: {}", name));
+        }
+        if name == SECRETS_FILE && app_data_dir_is_fallback() {
+            return Err("Refusing to restore credentials into the temporary fallback directory".to_string());
+        }
+
+        let mut entry = archive
+            .by_name(name)
+            .map_err(|_| format!("Backup manifest lists {} but the archive does not contain it", name))?;
+        let mut content = Vec::new();
+        entry
+            .read_to_end(&mut content)
+            .map_err(|e| format!("Failed to read {} from backup: {}", name, e))?;
+        // Compressed caches are checked by their decoded JSON, then restored byte for byte
+        cache_file::decode(content.clone())
+            .map_err(|e| e.to_string())
+            .and_then(|json| serde_json::from_str::<serde_json::Value>(&json).map_err(|e| e.to_string()))
+            .map_err(|e| format!("Backup file {} is not valid JSON: {}", name, e))?;
+
+        restored.push((name.clone(), content));
+    }
+
+    let data_dir = get_app_data_dir();
+    if !overwrite {
+        let existing: Vec<&str> = restored
+            .iter()
+            .map(|(name, _)| name.as_str())
+            .filter(|name| data_dir.join(name).exists())
+            .collect();
+        if !existing.is_empty() {
+            return Err(format!(
+                "Refusing to overwrite existing files: {}. Pass overwrite to replace them.",
+                existing.join(", ")
+            ));
+        }
+    }
+
+    fs::create_dir_all(&data_dir).map_err(|e| format!("Failed to create data directory: {}", e))?;
+    for (name, content) in &restored {
+        fs::write(data_dir.join(name), content).map_err(|e| format!("Failed to restore {}: {}", name, e))?;
+    }
+
+    println!("📦 Restored {} files from {}", restored.len(), path);
+    Ok(restored.into_iter().map(|(name, _)| name).collect())
+}
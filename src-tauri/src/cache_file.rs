@@ -0,0 +1,154 @@
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::settings::load_settings;
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Where the compressed form of `path` lives, e.g. `sophos_cache.json.gz`. Caches are
+/// addressed by their plain `.json` path everywhere else.
+pub(crate) fn compressed_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".gz");
+    PathBuf::from(name)
+}
+
+pub(crate) fn exists(path: &Path) -> bool {
+    path.exists() || compressed_path(path).exists()
+}
+
+/// Decodes as much of a possibly truncated cache as can be read. Used for salvage only,
+/// where a partial document is better than none.
+pub(crate) fn decode_lossy(bytes: Vec<u8>) -> String {
+    if bytes.starts_with(&GZIP_MAGIC) {
+        // On a truncated stream `read_to_end` fails, but keeps what it decoded so far
+        let mut content = Vec::new();
+        let _ = GzDecoder::new(bytes.as_slice()).read_to_end(&mut content);
+        String::from_utf8_lossy(&content).into_owned()
+    } else {
+        String::from_utf8_lossy(&bytes).into_owned()
+    }
+}
+
+/// Reads the raw bytes of whichever form of the cache is on disk.
+pub(crate) fn read_bytes(path: &Path) -> io::Result<Vec<u8>> {
+    let compressed = compressed_path(path);
+    if compressed.exists() {
+        fs::read(compressed)
+    } else {
+        fs::read(path)
+    }
+}
+
+/// Decodes cache bytes, going by the gzip magic bytes rather than the file name.
+pub(crate) fn decode(bytes: Vec<u8>) -> io::Result<String> {
+    if bytes.starts_with(&GZIP_MAGIC) {
+        let mut content = String::new();
+        GzDecoder::new(bytes.as_slice()).read_to_string(&mut content)?;
+        Ok(content)
+    } else {
+        String::from_utf8(bytes).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+}
+
+/// Reads a cache in whichever form is on disk, preferring the compressed one.
+pub(crate) fn read_text(path: &Path) -> io::Result<String> {
+    decode(read_bytes(path)?)
+}
+
+/// Writes to a sibling temp file and renames it over `path`, so a crash mid-write leaves
+/// either the old file or the new one, never a truncated mix.
+fn write_atomic(path: &Path, bytes: &[u8]) -> io::Result<()> {
+    let mut temp_name = path.as_os_str().to_owned();
+    temp_name.push(".tmp");
+    let temp_path = PathBuf::from(temp_name);
+
+    let result = fs::File::create(&temp_path)
+        .and_then(|mut file| file.write_all(bytes).and_then(|_| file.sync_all()))
+        .and_then(|_| fs::rename(&temp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Writes a cache in the encoding the settings ask for and removes the other form, so an
+/// existing cache switches format on its next write.
+pub(crate) fn write_text(path: &Path, content: &str) -> io::Result<()> {
+    let compressed = compressed_path(path);
+
+    if load_settings().compress_cache {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(content.as_bytes())?;
+        write_atomic(&compressed, &encoder.finish()?)?;
+        remove_if_present(path)
+    } else {
+        write_atomic(path, content.as_bytes())?;
+        remove_if_present(&compressed)
+    }
+}
+
+/// Removes both forms of a cache. Returns whether anything was deleted.
+pub(crate) fn remove(path: &Path) -> io::Result<bool> {
+    let existed = exists(path);
+    remove_if_present(path)?;
+    remove_if_present(&compressed_path(path))?;
+    Ok(existed)
+}
+
+fn remove_if_present(path: &Path) -> io::Result<()> {
+    match fs::remove_file(path) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::isolated_data_dir;
+
+    const ORIGINAL: &str = r#"{"endpoints":[{"id":"a"}]}"#;
+
+    fn temp_path(path: &Path) -> PathBuf {
+        let mut name = path.as_os_str().to_owned();
+        name.push(".tmp");
+        PathBuf::from(name)
+    }
+
+    #[test]
+    fn stale_temp_file_from_an_interrupted_write_is_ignored_and_replaced() {
+        let _data_dir = isolated_data_dir();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sophos_cache.json");
+        write_text(&path, ORIGINAL).unwrap();
+
+        // A crash mid-write leaves a truncated temp file next to the cache
+        fs::write(temp_path(&path), &ORIGINAL[..10]).unwrap();
+
+        assert_eq!(read_text(&path).unwrap(), ORIGINAL);
+
+        write_text(&path, r#"{"endpoints":[]}"#).unwrap();
+        assert_eq!(read_text(&path).unwrap(), r#"{"endpoints":[]}"#);
+        assert!(!temp_path(&path).exists());
+    }
+
+    #[test]
+    fn failed_write_leaves_the_original_intact() {
+        let _data_dir = isolated_data_dir();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sophos_cache.json");
+        write_text(&path, ORIGINAL).unwrap();
+
+        // The temp file can't be created while a directory occupies its name
+        fs::create_dir(temp_path(&path)).unwrap();
+
+        assert!(write_text(&path, r#"{"endpoints":[]}"#).is_err());
+        assert_eq!(read_text(&path).unwrap(), ORIGINAL);
+        serde_json::from_str::<serde_json::Value>(&read_text(&path).unwrap()).unwrap();
+    }
+}
@@ -0,0 +1,199 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::{cache_file, get_cache_path, load_credentials, read_cache_file, refresh_summary, CachedData, SophosEndpoint};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum CacheRepairStatus {
+    /// The cache parsed as-is; nothing was changed
+    Intact,
+    /// Endpoints were salvaged and the cache rewritten
+    Repaired,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct CacheRepairResult {
+    status: CacheRepairStatus,
+    recovered: usize,
+    /// Complete records that still didn't parse as endpoints
+    skipped: usize,
+    /// Copy of the damaged file, kept for support
+    backup_path: Option<String>,
+}
+
+/// Pulls every complete element out of the `"endpoints"` array, stopping at the first one
+/// cut off by truncation.
+fn salvage_endpoint_values(content: &str) -> Vec<serde_json::Value> {
+    let Some(key) = content.find("\"endpoints\"") else {
+        return Vec::new();
+    };
+    let Some(open) = content[key..].find('[') else {
+        return Vec::new();
+    };
+
+    let mut values = Vec::new();
+    let mut position = key + open + 1;
+    loop {
+        let rest = content[position..].trim_start_matches(|c: char| c.is_whitespace() || c == ',');
+        if rest.is_empty() || rest.starts_with(']') {
+            break;
+        }
+        position = content.len() - rest.len();
+
+        let mut stream = serde_json::Deserializer::from_str(rest).into_iter::<serde_json::Value>();
+        match stream.next() {
+            Some(Ok(value)) => {
+                position += stream.byte_offset();
+                values.push(value);
+            }
+            _ => break,
+        }
+    }
+    values
+}
+
+/// The value of a top-level string field, if it survived.
+fn salvage_string_field(content: &str, field: &str) -> Option<String> {
+    // Top-level fields follow the endpoint list, so search from the end
+    let key = content.rfind(&format!("\"{}\"", field))?;
+    let rest = content[key + field.len() + 2..].trim_start().strip_prefix(':')?.trim_start();
+    serde_json::Deserializer::from_str(rest)
+        .into_iter::<String>()
+        .next()?
+        .ok()
+}
+
+/// Salvages endpoints from a damaged `sophos_cache.json`, e.g. one truncated by a crash.
+/// The damaged file is kept as `sophos_cache.corrupt.json`. The repaired cache gets
+/// timestamp 0, so it serves local views but is refetched on the next fetch.
+#[tauri::command]
+pub(crate) async fn repair_cache() -> Result<CacheRepairResult, String> {
+    let cache_path = get_cache_path();
+    if !cache_file::exists(&cache_path) {
+        return Err("No cache file to repair".to_string());
+    }
+
+    let bytes = cache_file::read_bytes(&cache_path).map_err(|e| format!("Failed to read cache: {}", e))?;
+    if let Ok(content) = cache_file::decode(bytes.clone()) {
+        if serde_json::from_str::<CachedData>(&content).is_ok() {
+            return Ok(CacheRepairResult {
+                status: CacheRepairStatus::Intact,
+                recovered: 0,
+                skipped: 0,
+                backup_path: None,
+            });
+        }
+    }
+
+    let content = cache_file::decode_lossy(bytes.clone());
+    let values = salvage_endpoint_values(&content);
+    let total = values.len();
+    let endpoints: Vec<SophosEndpoint> = values
+        .into_iter()
+        .filter_map(|value| serde_json::from_value(value).ok())
+        .collect();
+    if endpoints.is_empty() {
+        return Err("Cache is damaged and no endpoints could be recovered. Clear the cache and fetch again.".to_string());
+    }
+
+    // Fields after the endpoint list are the first lost to truncation
+    let tenant_id = salvage_string_field(&content, "tenant_id")
+        .or_else(|| load_credentials().map(|credentials| credentials.tenant_id))
+        .ok_or_else(|| "Cannot tell which tenant the damaged cache belongs to".to_string())?;
+
+    let backup_path = cache_path.with_file_name("sophos_cache.corrupt.json");
+    fs::write(&backup_path, &bytes).map_err(|e| format!("Failed to keep a copy of the damaged cache: {}", e))?;
+
+    let cached_data = CachedData::new(endpoints, 0, tenant_id);
+    let json_content = serde_json::to_string_pretty(&cached_data)
+        .map_err(|e| format!("Failed to serialize cache: {}", e))?;
+    cache_file::write_text(&cache_path, &json_content).map_err(|e| format!("Failed to save cache: {}", e))?;
+    refresh_summary(&cache_path, &cached_data);
+
+    let recovered = cached_data.endpoints.len();
+    println!("🩹 Recovered {} endpoints from a damaged cache ({} skipped)", recovered, total - recovered);
+    Ok(CacheRepairResult {
+        status: CacheRepairStatus::Repaired,
+        recovered,
+        skipped: total - recovered,
+        backup_path: Some(backup_path.display().to_string()),
+    })
+}
+
+/// Checks the cached endpoints against the checksum stored with them. Caches written
+/// before checksums were added have nothing to compare and count as intact.
+#[tauri::command]
+pub(crate) async fn verify_cache_integrity() -> Result<bool, String> {
+    let cached_data = read_cache_file(&get_cache_path())?;
+
+    match cached_data.checksum_matches() {
+        Some(true) => Ok(true),
+        Some(false) => {
+            println!("⚠️  Cache checksum mismatch: the file was corrupted or edited on disk");
+            Ok(false)
+        }
+        None => {
+            println!("ℹ️  Cache has no checksum (written before v2), nothing to verify");
+            Ok(true)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{endpoint_json, isolated_data_dir};
+    use crate::{load_cached_data, save_cached_data, CacheWriteMode};
+
+    fn save_cache() {
+        let endpoints: Vec<SophosEndpoint> = [endpoint_json("a", "ws-a", "2024-05-01T00:00:00Z")]
+            .into_iter()
+            .map(|item| serde_json::from_value(item).unwrap())
+            .collect();
+        save_cached_data(&get_cache_path(), &endpoints, "tenant", CacheWriteMode::Replace);
+    }
+
+    /// Changes one byte inside a hostname, keeping the file valid JSON.
+    fn flip_hostname_byte() {
+        let path = get_cache_path();
+        let mut bytes = fs::read(&path).unwrap();
+        let at = bytes.windows(4).position(|window| window == b"ws-a").unwrap() + 3;
+        bytes[at] ^= 0x03;
+        fs::write(&path, bytes).unwrap();
+    }
+
+    #[tokio::test]
+    async fn intact_cache_verifies_and_loads() {
+        let _data_dir = isolated_data_dir();
+        save_cache();
+
+        assert_eq!(verify_cache_integrity().await, Ok(true));
+        assert_eq!(load_cached_data(&get_cache_path(), "tenant").map(|endpoints| endpoints.len()), Some(1));
+    }
+
+    #[tokio::test]
+    async fn corrupted_cache_fails_verification_and_is_refetched() {
+        let _data_dir = isolated_data_dir();
+        save_cache();
+        flip_hostname_byte();
+
+        // Still parses, so only the checksum can catch it
+        assert!(read_cache_file(&get_cache_path()).is_ok());
+        assert_eq!(verify_cache_integrity().await, Ok(false));
+        // No cached endpoints means the caller fetches fresh data
+        assert!(load_cached_data(&get_cache_path(), "tenant").is_none());
+    }
+
+    #[tokio::test]
+    async fn unparseable_cache_is_an_error() {
+        let _data_dir = isolated_data_dir();
+        save_cache();
+        let path = get_cache_path();
+        let bytes = fs::read(&path).unwrap();
+        fs::write(&path, &bytes[..bytes.len() / 2]).unwrap();
+
+        assert!(verify_cache_integrity().await.is_err());
+        assert!(load_cached_data(&path, "tenant").is_none());
+    }
+}
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::Mutex;
+
+use crate::rate_limit::rate_limiter;
+use crate::{describe_request_error, token_url, SophosTokenResponse, WHOAMI_URL};
+
+/// Built clients, keyed by the user agent they were built with, so a settings change
+/// takes effect on the next request without a restart. Managed by the app, so every
+/// command shares one connection pool.
+#[derive(Default)]
+pub(crate) struct ClientState {
+    pub(crate) plain: Mutex<Option<(String, reqwest::Client)>>,
+    pub(crate) pinned: Mutex<Option<(String, reqwest::Client)>>,
+}
+
+/// Status and body of a Sophos API response, before any interpretation.
+pub(crate) struct RawResponse {
+    pub(crate) status: u16,
+    pub(crate) body: String,
+}
+
+/// The transport calls the fetch logic depends on. Caching, pagination and error mapping
+/// stay in the callers, so they can run against any implementation of this trait.
+pub(crate) trait SophosClient: Send + Sync {
+    /// Exchanges client credentials for an access token.
+    fn get_token(
+        &self,
+        client_id: &str,
+        client_secret: &str,
+    ) -> impl Future<Output = Result<SophosTokenResponse, String>> + Send;
+
+    /// Requests one page of the endpoint list. `query` already carries paging parameters.
+    /// `Err` is a transport failure; API errors come back as a non-2xx `RawResponse`.
+    fn get_endpoints_page(
+        &self,
+        base_url: &str,
+        access_token: &str,
+        tenant_id: &str,
+        query: &[(&str, String)],
+    ) -> impl Future<Output = Result<RawResponse, String>> + Send;
+
+    /// Asks whoami who the token belongs to. Not tenant scoped, so no `X-Tenant-ID`.
+    fn get_whoami(&self, access_token: &str) -> impl Future<Output = Result<RawResponse, String>> + Send;
+
+    /// Reads a single tenant resource, such as one endpoint's detail.
+    fn get_resource(
+        &self,
+        url: &str,
+        access_token: &str,
+        tenant_id: &str,
+    ) -> impl Future<Output = Result<RawResponse, String>> + Send;
+
+    /// Posts `body` as JSON to a tenant action: alert actions, scans, isolation changes.
+    fn post_action(
+        &self,
+        url: &str,
+        access_token: &str,
+        tenant_id: &str,
+        body: &serde_json::Value,
+    ) -> impl Future<Output = Result<RawResponse, String>> + Send;
+
+    /// Deletes a tenant resource, such as an endpoint.
+    fn delete_resource(
+        &self,
+        url: &str,
+        access_token: &str,
+        tenant_id: &str,
+    ) -> impl Future<Output = Result<RawResponse, String>> + Send;
+}
+
+/// Sends `request` through the rate limiter and reads the whole response body.
+async fn send_raw(request: reqwest::RequestBuilder) -> Result<RawResponse, String> {
+    rate_limiter().acquire().await;
+    let response = request
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| describe_request_error(&e))?;
+
+    let status = response.status().as_u16();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+
+    Ok(RawResponse { status, body })
+}
+
+fn tenant_request(
+    request: reqwest::RequestBuilder,
+    access_token: &str,
+    tenant_id: &str,
+) -> reqwest::RequestBuilder {
+    request
+        .header("Authorization", format!("Bearer {}", access_token))
+        .header("X-Tenant-ID", tenant_id)
+}
+
+impl SophosClient for reqwest::Client {
+    async fn get_token(&self, client_id: &str, client_secret: &str) -> Result<SophosTokenResponse, String> {
+        let mut params = HashMap::new();
+        params.insert("grant_type", "client_credentials");
+        params.insert("client_id", client_id);
+        params.insert("client_secret", client_secret);
+        params.insert("scope", "token");
+
+        rate_limiter().acquire().await;
+        let response = self
+            .post(token_url())
+            .form(&params)
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", describe_request_error(&e)))?;
+
+        if !response.status().is_success() {
+            return Err(format!("Authentication failed: {}", response.status()));
+        }
+
+        response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse response: {}", e))
+    }
+
+    async fn get_endpoints_page(
+        &self,
+        base_url: &str,
+        access_token: &str,
+        tenant_id: &str,
+        query: &[(&str, String)],
+    ) -> Result<RawResponse, String> {
+        send_raw(tenant_request(self.get(base_url).query(query), access_token, tenant_id)).await
+    }
+
+    async fn get_whoami(&self, access_token: &str) -> Result<RawResponse, String> {
+        send_raw(self.get(WHOAMI_URL).header("Authorization", format!("Bearer {}", access_token))).await
+    }
+
+    async fn get_resource(&self, url: &str, access_token: &str, tenant_id: &str) -> Result<RawResponse, String> {
+        send_raw(tenant_request(self.get(url), access_token, tenant_id)).await
+    }
+
+    async fn post_action(
+        &self,
+        url: &str,
+        access_token: &str,
+        tenant_id: &str,
+        body: &serde_json::Value,
+    ) -> Result<RawResponse, String> {
+        send_raw(tenant_request(self.post(url).json(body), access_token, tenant_id)).await
+    }
+
+    async fn delete_resource(&self, url: &str, access_token: &str, tenant_id: &str) -> Result<RawResponse, String> {
+        send_raw(tenant_request(self.delete(url), access_token, tenant_id)).await
+    }
+}
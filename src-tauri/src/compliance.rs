@@ -0,0 +1,295 @@
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+
+use crate::api_host::resolve_endpoints_base_url;
+use crate::rate_limit::rate_limiter;
+use crate::{
+    describe_api_error, fetch_endpoint_detail, fetch_with_saved_credentials, http_client, keep_latest_by,
+    load_cached_endpoints, require_credentials, SophosEndpoint,
+};
+
+/// Upper bound on simultaneous per-endpoint detail requests.
+const DETAIL_CONCURRENCY: usize = 5;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct ComplianceProgress {
+    completed: usize,
+    total: usize,
+}
+
+/// True when the product's `assignedProducts` entry has `status` `installed` and, if it
+/// reports an `updateStatus`, that is `upToDate`. Any other status, such as
+/// `notInstalled` or an outdated `updateStatus`, counts as not installed.
+fn has_product_installed(detail: &serde_json::Value, product: &str) -> bool {
+    detail
+        .get("assignedProducts")
+        .and_then(|products| products.as_array())
+        .map(|products| {
+            products.iter().any(|entry| {
+                let text = |key: &str| entry.get(key).and_then(|value| value.as_str()).map(str::trim);
+                text("code") == Some(product)
+                    && text("status") == Some("installed")
+                    && text("updateStatus").is_none_or(|status| status.eq_ignore_ascii_case("upToDate"))
+            })
+        })
+        .unwrap_or(false)
+}
+
+/// Returns endpoints where `product` (e.g. `coreAgent`, `interceptX`) is missing, not in
+/// the `installed` state, or not up to date (see `has_product_installed`). Emits
+/// `sophos-compliance-progress` as details come back.
+#[tauri::command]
+pub(crate) async fn find_endpoints_missing_product(
+    app: tauri::AppHandle,
+    access_token: String,
+    product: String,
+) -> Result<Vec<SophosEndpoint>, String> {
+    let credentials = require_credentials()?;
+    let endpoints = fetch_with_saved_credentials(access_token.clone()).await?;
+
+    let client = http_client()?;
+    let base_url = resolve_endpoints_base_url(&client, &access_token, &credentials).await?;
+    let total = endpoints.len();
+
+    println!("🔍 Checking {} endpoints for product '{}'", total, product);
+
+    let mut results = stream::iter(endpoints)
+        .map(|endpoint| {
+            let client = client.clone();
+            let base_url = base_url.clone();
+            let access_token = access_token.clone();
+            let tenant_id = credentials.tenant_id.clone();
+            async move {
+                let detail =
+                    fetch_endpoint_detail(&client, &base_url, &access_token, &tenant_id, &endpoint.id).await;
+                (endpoint, detail)
+            }
+        })
+        .buffer_unordered(DETAIL_CONCURRENCY);
+
+    let mut missing = Vec::new();
+    let mut completed = 0;
+    while let Some((endpoint, detail)) = results.next().await {
+        completed += 1;
+        match detail {
+            Ok(detail) => {
+                if !has_product_installed(&detail, &product) {
+                    missing.push(endpoint);
+                }
+            }
+            Err(e) => println!("   ⚠️  Skipping endpoint {}: {}", endpoint.id, e),
+        }
+        app.emit("sophos-compliance-progress", ComplianceProgress { completed, total })
+            .ok();
+    }
+
+    println!("📊 {} of {} endpoints are missing '{}'", missing.len(), total, product);
+    Ok(missing)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct InstalledApp {
+    name: String,
+    version: Option<String>,
+    #[serde(alias = "publisher")]
+    vendor: Option<String>,
+    #[serde(rename(deserialize = "installDate"), alias = "install_date")]
+    install_date: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ApplicationsPage {
+    items: Option<Vec<InstalledApp>>,
+    pages: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ApplicationInventory {
+    /// False when the endpoint or license doesn't expose application inventory
+    supported: bool,
+    applications: Vec<InstalledApp>,
+}
+
+#[tauri::command]
+pub(crate) async fn fetch_endpoint_applications(
+    access_token: String,
+    endpoint_id: String,
+) -> Result<ApplicationInventory, String> {
+    let credentials = require_credentials()?;
+    let client = http_client()?;
+    let base_url = resolve_endpoints_base_url(&client, &access_token, &credentials).await?;
+    let url = format!("{}/{}/applications", base_url, endpoint_id);
+
+    let mut applications = Vec::new();
+    let mut page_token: Option<String> = None;
+
+    loop {
+        let mut request = client.get(&url);
+        if let Some(ref token) = page_token {
+            request = request.query(&[("pageFromKey", token)]);
+        }
+
+        rate_limiter().acquire().await;
+        let response = request
+            .header("Authorization", format!("Bearer {}", &access_token))
+            .header("X-Tenant-ID", &credentials.tenant_id)
+            .header("Accept", "application/json")
+            .send()
+            .await
+            .map_err(|e| format!("Request failed: {}", e))?;
+
+        let status = response.status();
+
+        // Not every endpoint type or license tier has application inventory
+        if status == reqwest::StatusCode::NOT_FOUND || status == reqwest::StatusCode::FORBIDDEN {
+            println!("ℹ️  Application inventory not available for endpoint {} ({})", endpoint_id, status);
+            return Ok(ApplicationInventory {
+                supported: false,
+                applications: Vec::new(),
+            });
+        }
+
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(format!(
+                "Application inventory request failed ({}): {}",
+                status,
+                describe_api_error(&error_text)
+            ));
+        }
+
+        let page: ApplicationsPage = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse application inventory: {}", e))?;
+
+        applications.extend(page.items.unwrap_or_default());
+
+        match page
+            .pages
+            .as_ref()
+            .and_then(|pages| pages.get("nextKey"))
+            .and_then(|next_key| next_key.as_str())
+        {
+            Some(next_key) => page_token = Some(next_key.to_string()),
+            None => break,
+        }
+    }
+
+    println!("📦 Endpoint {} has {} installed applications", endpoint_id, applications.len());
+    Ok(ApplicationInventory {
+        supported: true,
+        applications,
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct CoverageReport {
+    /// Expected hostnames with a matching endpoint in Sophos
+    covered: Vec<String>,
+    /// Expected hostnames Sophos doesn't know about
+    missing: Vec<String>,
+    /// Endpoints in Sophos that aren't on the expected list, including any without a hostname
+    unexpected: Vec<SophosEndpoint>,
+}
+
+/// Lowercased hostname and its short form: `WS-042.corp.example.com` gives
+/// `ws-042.corp.example.com` and `ws-042`.
+fn hostname_keys(hostname: &str) -> (String, String) {
+    let full = hostname.trim().to_lowercase();
+    let short = full.split('.').next().unwrap_or_default().to_string();
+    (full, short)
+}
+
+/// Compares the cached inventory against an expected hostname list (e.g. from AD or a
+/// CMDB), case-insensitively. A name matches on the full hostname or, since directory
+/// exports and Sophos disagree on domain suffixes, on the short name. Expected names are
+/// deduplicated, and re-enrolled machines in the cache count once (latest record).
+#[tauri::command]
+pub(crate) async fn compute_coverage(expected_hostnames: Vec<String>) -> Result<CoverageReport, String> {
+    let endpoints = keep_latest_by(load_cached_endpoints()?, SophosEndpoint::hostname_key);
+
+    let mut expected: Vec<(String, (String, String))> = Vec::new();
+    for hostname in expected_hostnames {
+        let hostname = hostname.trim().to_string();
+        let keys = hostname_keys(&hostname);
+        if hostname.is_empty() || expected.iter().any(|(_, existing)| existing.0 == keys.0) {
+            continue;
+        }
+        expected.push((hostname, keys));
+    }
+
+    let cached_keys: Vec<Option<(String, String)>> = endpoints
+        .iter()
+        .map(|endpoint| endpoint.hostname_key().as_deref().map(hostname_keys))
+        .collect();
+    let matches = |(expected_full, expected_short): &(String, String), (full, short): &(String, String)| {
+        expected_full == full || expected_short == short
+    };
+
+    let mut covered = Vec::new();
+    let mut missing = Vec::new();
+    for (hostname, keys) in &expected {
+        if cached_keys.iter().flatten().any(|cached| matches(keys, cached)) {
+            covered.push(hostname.clone());
+        } else {
+            missing.push(hostname.clone());
+        }
+    }
+
+    let unexpected: Vec<SophosEndpoint> = endpoints
+        .into_iter()
+        .zip(cached_keys)
+        .filter(|(_, cached)| match cached {
+            Some(cached) => !expected.iter().any(|(_, keys)| matches(keys, cached)),
+            None => true,
+        })
+        .map(|(endpoint, _)| endpoint)
+        .collect();
+
+    println!(
+        "📋 Coverage: {} covered, {} missing, {} unexpected",
+        covered.len(),
+        missing.len(),
+        unexpected.len()
+    );
+    Ok(CoverageReport {
+        covered,
+        missing,
+        unexpected,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn detail(products: serde_json::Value) -> serde_json::Value {
+        serde_json::json!({ "id": "a", "assignedProducts": products })
+    }
+
+    #[test]
+    fn installed_and_current_product_counts() {
+        let detail = detail(serde_json::json!([
+            { "code": "coreAgent", "version": "2024.1.0", "status": "installed" },
+            { "code": "interceptX", "version": "2024.1.0", "status": "installed", "updateStatus": "upToDate" },
+        ]));
+
+        assert!(has_product_installed(&detail, "coreAgent"));
+        assert!(has_product_installed(&detail, "interceptX"));
+    }
+
+    #[test]
+    fn missing_uninstalled_or_outdated_product_does_not_count() {
+        let detail = detail(serde_json::json!([
+            { "code": "coreAgent", "status": "notInstalled" },
+            { "code": "interceptX", "status": "installed", "updateStatus": "updateFailed" },
+        ]));
+
+        assert!(!has_product_installed(&detail, "coreAgent"));
+        assert!(!has_product_installed(&detail, "interceptX"));
+        assert!(!has_product_installed(&detail, "deviceEncryption"));
+        assert!(!has_product_installed(&serde_json::json!({ "id": "a" }), "coreAgent"));
+    }
+}
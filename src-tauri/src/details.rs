@@ -0,0 +1,67 @@
+use futures::stream::{self, StreamExt};
+
+use crate::api_host::resolve_endpoints_base_url;
+use crate::{fetch_endpoint_detail, http_client, require_credentials, SophosError};
+
+/// Ceiling for caller-chosen concurrency; the shared rate limiter still applies below it.
+const MAX_DETAIL_CONCURRENCY: usize = 10;
+
+/// Fetches the detail record for each id, at most `concurrency` at a time, in completion
+/// order. Every id gets its own result; one failure never aborts the rest.
+pub(crate) async fn fetch_details_bulk(
+    client: &reqwest::Client,
+    base_url: &str,
+    access_token: &str,
+    tenant_id: &str,
+    endpoint_ids: Vec<String>,
+    concurrency: usize,
+) -> Vec<(String, Result<serde_json::Value, SophosError>)> {
+    stream::iter(endpoint_ids)
+        .map(|endpoint_id| async move {
+            let detail = fetch_endpoint_detail(client, base_url, access_token, tenant_id, &endpoint_id).await;
+            (endpoint_id, detail)
+        })
+        .buffer_unordered(concurrency.clamp(1, MAX_DETAIL_CONCURRENCY))
+        .collect()
+        .await
+}
+
+/// Detail records for many endpoints at once. Duplicate ids are fetched once, and
+/// `concurrency` is clamped to 1..=10.
+#[tauri::command]
+pub(crate) async fn fetch_endpoint_details_bulk(
+    access_token: String,
+    endpoint_ids: Vec<String>,
+    concurrency: usize,
+) -> Result<Vec<(String, Result<serde_json::Value, String>)>, String> {
+    let credentials = require_credentials()?;
+    let client = http_client()?;
+    let base_url = resolve_endpoints_base_url(&client, &access_token, &credentials).await?;
+
+    let mut endpoint_ids: Vec<String> = endpoint_ids
+        .into_iter()
+        .map(|id| id.trim().to_string())
+        .filter(|id| !id.is_empty())
+        .collect();
+    endpoint_ids.sort();
+    endpoint_ids.dedup();
+
+    let requested = endpoint_ids.len();
+    let results = fetch_details_bulk(
+        &client,
+        &base_url,
+        &access_token,
+        &credentials.tenant_id,
+        endpoint_ids,
+        concurrency,
+    )
+    .await;
+
+    let failed = results.iter().filter(|(_, detail)| detail.is_err()).count();
+    println!("📋 Fetched details for {} of {} endpoints", requested - failed, requested);
+
+    Ok(results
+        .into_iter()
+        .map(|(endpoint_id, detail)| (endpoint_id, detail.map_err(|e| e.to_string())))
+        .collect())
+}
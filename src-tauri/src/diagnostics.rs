@@ -0,0 +1,430 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::{Duration, Instant};
+
+use crate::api_host::{cached_api_host, resolve_endpoints_base_url};
+use crate::client::SophosClient;
+use crate::migrations::schema_version_of;
+use crate::rate_limit::rate_limiter;
+use crate::settings::{load_settings, MAX_PAGE_DELAY_MS};
+use crate::timings::last_fetch_timings;
+use crate::token::cached_token_client_id;
+use crate::{
+    describe_api_error, endpoints_base_url, get_cache_path, get_secrets_path, http_client, load_credentials,
+    read_cache_file, require_credentials, token_url, SecretsFile, SophosCredentials, SophosEndpoint, SophosError,
+    SECRETS_SCHEMA_VERSION,
+};
+
+const IDENTITY_HOST: &str = "id.sophos.com";
+const DIAGNOSTIC_TIMEOUT_SECS: u64 = 10;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct DiagnosticStep {
+    name: String,
+    passed: bool,
+    latency_ms: u64,
+    status: Option<u16>,
+    detail: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct DiagnosticsReport {
+    steps: Vec<DiagnosticStep>,
+    all_passed: bool,
+}
+
+/// Any 4xx means the request reached Sophos and was rejected on its merits, which is
+/// what we expect when probing without valid credentials. 5xx points at Sophos itself.
+fn interpret_probe(name: &str, started: Instant, result: Result<reqwest::Response, reqwest::Error>) -> DiagnosticStep {
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    match result {
+        Ok(response) => {
+            let status = response.status();
+            let passed = status.is_success() || status.is_client_error();
+            let detail = if status.is_client_error() {
+                format!("Reachable (rejected unauthenticated probe with {} as expected)", status)
+            } else if status.is_success() {
+                "Reachable".to_string()
+            } else {
+                format!("Reached host but it returned {}", status)
+            };
+            DiagnosticStep {
+                name: name.to_string(),
+                passed,
+                latency_ms,
+                status: Some(status.as_u16()),
+                detail: Some(detail),
+            }
+        }
+        Err(e) => DiagnosticStep {
+            name: name.to_string(),
+            passed: false,
+            latency_ms,
+            status: None,
+            detail: Some(format!("Request failed: {}", e)),
+        },
+    }
+}
+
+async fn check_dns() -> DiagnosticStep {
+    let name = format!("DNS resolution of {}", IDENTITY_HOST);
+    let started = Instant::now();
+    let lookup = tokio::time::timeout(
+        Duration::from_secs(DIAGNOSTIC_TIMEOUT_SECS),
+        tokio::net::lookup_host((IDENTITY_HOST, 443)),
+    )
+    .await;
+    let latency_ms = started.elapsed().as_millis() as u64;
+
+    let (passed, detail) = match lookup {
+        Ok(Ok(mut addresses)) => match addresses.next() {
+            Some(address) => (true, format!("Resolved to {}", address.ip())),
+            None => (false, "Lookup returned no addresses".to_string()),
+        },
+        Ok(Err(e)) => (false, format!("Lookup failed: {}", e)),
+        Err(_) => (false, format!("Lookup timed out after {}s", DIAGNOSTIC_TIMEOUT_SECS)),
+    };
+
+    DiagnosticStep {
+        name,
+        passed,
+        latency_ms,
+        status: None,
+        detail: Some(detail),
+    }
+}
+
+async fn check_token_endpoint(client: &reqwest::Client) -> DiagnosticStep {
+    // Deliberately invalid credentials: a 400/401 proves the endpoint is reachable
+    let params = [
+        ("grant_type", "client_credentials"),
+        ("client_id", "connectivity-check"),
+        ("client_secret", "connectivity-check"),
+        ("scope", "token"),
+    ];
+
+    rate_limiter().acquire().await;
+    let started = Instant::now();
+    let result = client
+        .post(token_url())
+        .timeout(Duration::from_secs(DIAGNOSTIC_TIMEOUT_SECS))
+        .form(&params)
+        .send()
+        .await;
+    interpret_probe("Token endpoint reachability", started, result)
+}
+
+async fn check_api_host(client: &reqwest::Client) -> DiagnosticStep {
+    let name = "Region API host reachability";
+    let Some(credentials) = load_credentials() else {
+        return DiagnosticStep {
+            name: name.to_string(),
+            passed: false,
+            latency_ms: 0,
+            status: None,
+            detail: Some("Skipped: no saved credentials to determine the region".to_string()),
+        };
+    };
+
+    // No token here to ask whoami with, so probe the host it last reported, if any
+    let api_host = cached_api_host(&credentials.tenant_id);
+    let url = endpoints_base_url(&credentials.region, api_host.as_deref());
+    rate_limiter().acquire().await;
+    let started = Instant::now();
+    let result = client
+        .get(&url)
+        .timeout(Duration::from_secs(DIAGNOSTIC_TIMEOUT_SECS))
+        .header("Accept", "application/json")
+        .send()
+        .await;
+    interpret_probe(name, started, result)
+}
+
+#[tauri::command]
+pub(crate) async fn run_connectivity_diagnostics() -> Result<DiagnosticsReport, String> {
+    let client = http_client()?;
+
+    let steps = vec![
+        check_dns().await,
+        check_token_endpoint(&client).await,
+        check_api_host(&client).await,
+    ];
+    let all_passed = steps.iter().all(|step| step.passed);
+
+    for step in &steps {
+        println!("   {} {}", if step.passed { "✅" } else { "❌" }, step.name);
+    }
+
+    Ok(DiagnosticsReport { steps, all_passed })
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ConsistencyReport {
+    credentials_tenant_id: Option<String>,
+    cache_tenant_id: Option<String>,
+    /// Whether the persisted token was issued to the current client id; `None` when no
+    /// token is cached
+    token_matches_credentials: Option<bool>,
+    consistent: bool,
+    /// One line per mismatch, each with the recommended fix
+    issues: Vec<String>,
+}
+
+/// Compares the active credentials against the tenant of the endpoint cache and the client
+/// the cached token was issued to, to explain data showing up for the wrong tenant.
+#[tauri::command]
+pub(crate) async fn check_config_consistency() -> Result<ConsistencyReport, String> {
+    let credentials = load_credentials();
+    let cache_tenant_id = read_cache_file(&get_cache_path()).ok().map(|cached| cached.tenant_id);
+    let token_client_id = cached_token_client_id();
+
+    let mut issues = Vec::new();
+    let Some(credentials) = credentials else {
+        if cache_tenant_id.is_some() || token_client_id.is_some() {
+            issues.push("Cached data exists but no credentials are saved; clear the cache or save credentials".to_string());
+        }
+        return Ok(ConsistencyReport {
+            credentials_tenant_id: None,
+            cache_tenant_id,
+            token_matches_credentials: None,
+            consistent: issues.is_empty(),
+            issues,
+        });
+    };
+
+    if let Some(cache_tenant_id) = cache_tenant_id.as_deref().filter(|id| *id != credentials.tenant_id) {
+        issues.push(format!(
+            "Endpoint cache belongs to tenant {} but the credentials are for tenant {}; clear the cache",
+            cache_tenant_id, credentials.tenant_id
+        ));
+    }
+
+    let token_matches_credentials = token_client_id.map(|client_id| client_id == credentials.client_id);
+    if token_matches_credentials == Some(false) {
+        issues.push("Cached token was issued to a different client id; invalidate the token".to_string());
+    }
+
+    for issue in &issues {
+        println!("⚠️  {}", issue);
+    }
+
+    Ok(ConsistencyReport {
+        credentials_tenant_id: Some(credentials.tenant_id),
+        cache_tenant_id,
+        token_matches_credentials,
+        consistent: issues.is_empty(),
+        issues,
+    })
+}
+
+/// Largest page Sophos serves from the endpoint list.
+const MAX_PAGE_SIZE: u32 = 500;
+
+/// Requests the first page of the endpoint list, with totals, as parsed JSON.
+async fn first_page(access_token: &str, page_size: u32) -> Result<serde_json::Value, String> {
+    let credentials = require_credentials()?;
+    let client = http_client()?;
+    let base_url = resolve_endpoints_base_url(&client, access_token, &credentials).await?;
+    let response = client
+        .get_endpoints_page(
+            &base_url,
+            access_token,
+            &credentials.tenant_id,
+            &[("pageSize", page_size.to_string()), ("pageTotal", "true".to_string())],
+        )
+        .await?;
+
+    if !(200..300).contains(&response.status) {
+        return Err(format!(
+            "First page request failed ({}): {}",
+            response.status,
+            describe_api_error(&response.body)
+        ));
+    }
+
+    serde_json::from_str(&response.body).map_err(|e| format!("Failed to parse first page: {}", e))
+}
+
+/// Requests one page of the endpoint list and returns the response as parsed JSON,
+/// `pages` included. Nothing is cached or deduplicated, so support can inspect the real
+/// API shape for a tenant. `page_size` defaults to the `page_size` setting.
+#[tauri::command]
+pub(crate) async fn fetch_first_page(
+    access_token: String,
+    page_size: Option<u32>,
+) -> Result<serde_json::Value, String> {
+    let page_size = page_size.unwrap_or_else(|| load_settings().page_size);
+    if !(1..=MAX_PAGE_SIZE).contains(&page_size) {
+        return Err(format!("Page size must be between 1 and {}, got {}", MAX_PAGE_SIZE, page_size));
+    }
+
+    first_page(&access_token, page_size).await
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct FetchEstimate {
+    total_endpoints: u64,
+    estimated_pages: u64,
+    estimated_seconds: f64,
+}
+
+/// Estimates how long a full fetch would take: the page count from the first page's
+/// totals, times the per-page cost. That cost is the average latency of the last fetch
+/// (or of this first page when there is none) plus the page delay, but never less than
+/// the rate limit allows.
+#[tauri::command]
+pub(crate) async fn estimate_fetch(access_token: String) -> Result<FetchEstimate, String> {
+    let settings = load_settings();
+    let page_size = settings.page_size.clamp(1, MAX_PAGE_SIZE);
+
+    let started = Instant::now();
+    let page = first_page(&access_token, page_size).await?;
+    let probe_latency_ms = started.elapsed().as_millis() as u64;
+
+    let total_endpoints = page
+        .get("pages")
+        .and_then(|pages| pages.get("items"))
+        .and_then(|items| items.as_u64())
+        .ok_or("Sophos did not report a total endpoint count")?;
+    let estimated_pages = total_endpoints.div_ceil(u64::from(page_size)).max(1);
+
+    let latency_ms = last_fetch_timings()
+        .map(|timings| timings.average_page_latency_ms())
+        .filter(|latency| *latency > 0)
+        .unwrap_or(probe_latency_ms);
+    let page_delay_ms = settings.page_delay_ms.min(MAX_PAGE_DELAY_MS);
+    let rate_limit_ms = match settings.max_requests_per_minute {
+        0 => 0,
+        rate => 60_000 / u64::from(rate),
+    };
+    let per_page_ms = (latency_ms + page_delay_ms).max(rate_limit_ms);
+
+    // No delay follows the last page
+    let total_ms = estimated_pages * per_page_ms - page_delay_ms.min(per_page_ms);
+    Ok(FetchEstimate {
+        total_endpoints,
+        estimated_pages,
+        estimated_seconds: total_ms as f64 / 1000.0,
+    })
+}
+
+/// What's wrong with the secrets file, specific enough to point the user at the fix.
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub(crate) enum SecretsDiagnostic {
+    Missing { path: String },
+    Unreadable { path: String, message: String },
+    InvalidJson { line: usize, column: usize, message: String },
+    MissingField { field: String, line: usize, column: usize },
+    InvalidValue { message: String },
+}
+
+/// Classifies a serde error from reading the secrets file into a diagnostic.
+fn secrets_parse_diagnostic(error: serde_json::Error) -> SecretsDiagnostic {
+    let message = error.to_string();
+
+    if error.is_syntax() || error.is_eof() {
+        return SecretsDiagnostic::InvalidJson {
+            line: error.line(),
+            column: error.column(),
+            message,
+        };
+    }
+
+    // serde reports these as "missing field `client_id` at line 4 column 1"
+    if let Some(field) = message
+        .strip_prefix("missing field `")
+        .and_then(|rest| rest.split('`').next())
+    {
+        return SecretsDiagnostic::MissingField {
+            field: field.to_string(),
+            line: error.line(),
+            column: error.column(),
+        };
+    }
+
+    SecretsDiagnostic::InvalidValue { message }
+}
+
+/// Checks the secrets file without loading it, so a typo surfaces as a specific problem
+/// instead of "no credentials saved".
+#[tauri::command]
+pub(crate) async fn diagnose_secrets_file() -> Result<(), SecretsDiagnostic> {
+    let path = get_secrets_path();
+    if !path.exists() {
+        return Err(SecretsDiagnostic::Missing {
+            path: path.display().to_string(),
+        });
+    }
+
+    let content = fs::read_to_string(&path).map_err(|e| SecretsDiagnostic::Unreadable {
+        path: path.display().to_string(),
+        message: e.to_string(),
+    })?;
+
+    let value: serde_json::Value = serde_json::from_str(&content).map_err(secrets_parse_diagnostic)?;
+
+    // Re-parse from the text rather than the value so errors keep their line and column
+    match schema_version_of(&value, 1) {
+        1 => {
+            serde_json::from_str::<SophosCredentials>(&content).map_err(secrets_parse_diagnostic)?;
+        }
+        SECRETS_SCHEMA_VERSION => {
+            let secrets = serde_json::from_str::<SecretsFile>(&content).map_err(secrets_parse_diagnostic)?;
+            if !secrets.profiles.contains_key(&secrets.active_profile) {
+                return Err(SecretsDiagnostic::InvalidValue {
+                    message: format!("Active profile '{}' is not defined in profiles", secrets.active_profile),
+                });
+            }
+        }
+        version => {
+            return Err(SecretsDiagnostic::InvalidValue {
+                message: format!(
+                    "Unsupported secrets schema v{} (this version supports up to v{})",
+                    version, SECRETS_SCHEMA_VERSION
+                ),
+            })
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the error (or empty result) a real fetch would for `kind`: `auth`, `network`,
+/// `rate_limit`, `parse`, `region` or `empty`, so the UI's error states can be exercised
+/// without touching the network or the credentials. Development builds only.
+#[tauri::command]
+pub(crate) async fn simulate_fetch_error(kind: String) -> Result<Vec<SophosEndpoint>, String> {
+    if !cfg!(debug_assertions) {
+        return Err("simulate_fetch_error is only available in development builds".to_string());
+    }
+
+    println!("🧪 Simulating fetch result '{}'", kind.trim());
+    let error = match kind.trim().to_lowercase().as_str() {
+        "auth" => SophosError::Api {
+            status: 401,
+            message: "API request failed on page 1 (401 Unauthorized): Simulated authentication failure".to_string(),
+        },
+        "network" => SophosError::Request(
+            "Request failed on page 1: Simulated network failure (connection refused)".to_string(),
+        ),
+        "rate_limit" => SophosError::Api {
+            status: 429,
+            message: "API request failed on page 1 (429 Too Many Requests): Simulated rate limit".to_string(),
+        },
+        "parse" => SophosError::Parse("Failed to parse response on page 1: Simulated malformed JSON".to_string()),
+        "region" => SophosError::RegionMismatch {
+            message: "API request failed on page 1 (403 Forbidden): Simulated region mismatch".to_string(),
+        },
+        "empty" => return Ok(Vec::new()),
+        other => {
+            return Err(format!(
+                "Unknown error kind '{}'. Use auth, network, rate_limit, parse, region or empty.",
+                other
+            ))
+        }
+    };
+
+    Err(error.into())
+}
@@ -0,0 +1,138 @@
+use serde::{Deserialize, Serialize};
+use std::net::Ipv4Addr;
+use std::time::Duration;
+
+use crate::load_cached_endpoints;
+
+/// Per-address limit, so an unreachable DNS server can't hang the command.
+const REVERSE_LOOKUP_TIMEOUT: Duration = Duration::from_secs(3);
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum DnsStatus {
+    Resolved,
+    /// The resolver answered, but there is no PTR record for the address
+    NoPtr,
+    TimedOut,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct DnsResult {
+    ip: String,
+    hostname: Option<String>,
+    status: DnsStatus,
+}
+
+/// Reverse lookup through the system resolver. `NI_NAMEREQD` makes a missing PTR record
+/// an error instead of echoing the address back.
+#[cfg(unix)]
+fn reverse_lookup_blocking(ip: Ipv4Addr) -> Option<String> {
+    // SAFETY: sockaddr_in is plain data; zeroed is a valid starting value
+    let mut addr: libc::sockaddr_in = unsafe { std::mem::zeroed() };
+    addr.sin_family = libc::AF_INET as libc::sa_family_t;
+    addr.sin_addr.s_addr = u32::from(ip).to_be();
+    #[cfg(any(target_os = "macos", target_os = "ios", target_os = "freebsd", target_os = "openbsd", target_os = "netbsd"))]
+    {
+        addr.sin_len = std::mem::size_of::<libc::sockaddr_in>() as u8;
+    }
+
+    let mut host = [0 as libc::c_char; libc::NI_MAXHOST as usize];
+    // SAFETY: addr and host outlive the call and their exact sizes are passed alongside
+    let result = unsafe {
+        libc::getnameinfo(
+            &addr as *const libc::sockaddr_in as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_in>() as libc::socklen_t,
+            host.as_mut_ptr(),
+            host.len() as libc::socklen_t,
+            std::ptr::null_mut(),
+            0,
+            libc::NI_NAMEREQD,
+        )
+    };
+    if result != 0 {
+        return None;
+    }
+
+    // SAFETY: on success getnameinfo wrote a NUL-terminated name into host
+    let name = unsafe { std::ffi::CStr::from_ptr(host.as_ptr()) };
+    Some(name.to_string_lossy().into_owned())
+}
+
+/// Reverse lookup through the system resolver. `NI_NAMEREQD` makes a missing PTR record
+/// an error instead of echoing the address back.
+#[cfg(windows)]
+fn reverse_lookup_blocking(ip: Ipv4Addr) -> Option<String> {
+    use windows_sys::Win32::Networking::WinSock::{
+        getnameinfo, WSAStartup, AF_INET, NI_MAXHOST, NI_NAMEREQD, SOCKADDR, SOCKADDR_IN, WSADATA,
+    };
+
+    // WSAStartup is reference counted, so calling it again here is harmless
+    // SAFETY: WSADATA is plain data filled in by the call
+    let mut wsa_data: WSADATA = unsafe { std::mem::zeroed() };
+    if unsafe { WSAStartup(0x0202, &mut wsa_data) } != 0 {
+        return None;
+    }
+
+    // SAFETY: SOCKADDR_IN is plain data; zeroed is a valid starting value
+    let mut addr: SOCKADDR_IN = unsafe { std::mem::zeroed() };
+    addr.sin_family = AF_INET;
+    addr.sin_addr.S_un.S_addr = u32::from(ip).to_be();
+
+    let mut host = [0u8; NI_MAXHOST as usize];
+    // SAFETY: addr and host outlive the call and their exact sizes are passed alongside
+    let result = unsafe {
+        getnameinfo(
+            &addr as *const SOCKADDR_IN as *const SOCKADDR,
+            std::mem::size_of::<SOCKADDR_IN>() as i32,
+            host.as_mut_ptr(),
+            host.len() as u32,
+            std::ptr::null_mut(),
+            0,
+            NI_NAMEREQD as i32,
+        )
+    };
+    if result != 0 {
+        return None;
+    }
+
+    let len = host.iter().position(|&byte| byte == 0).unwrap_or(host.len());
+    Some(String::from_utf8_lossy(&host[..len]).into_owned())
+}
+
+async fn reverse_lookup(ip: Ipv4Addr) -> DnsResult {
+    let lookup = tokio::task::spawn_blocking(move || reverse_lookup_blocking(ip));
+
+    let (hostname, status) = match tokio::time::timeout(REVERSE_LOOKUP_TIMEOUT, lookup).await {
+        Ok(Ok(Some(hostname))) => (Some(hostname), DnsStatus::Resolved),
+        Ok(_) => (None, DnsStatus::NoPtr),
+        // The blocking lookup keeps running in the background until the resolver gives up
+        Err(_) => (None, DnsStatus::TimedOut),
+    };
+
+    DnsResult {
+        ip: ip.to_string(),
+        hostname,
+        status,
+    }
+}
+
+/// Reverse DNS for each IPv4 address of a cached endpoint, looked up concurrently.
+/// Addresses that don't parse are skipped.
+#[tauri::command]
+pub(crate) async fn resolve_endpoint_dns(endpoint_id: String) -> Result<Vec<DnsResult>, String> {
+    let endpoint = load_cached_endpoints()?
+        .into_iter()
+        .find(|endpoint| endpoint.id == endpoint_id)
+        .ok_or_else(|| format!("Endpoint {} is not in the cache", endpoint_id))?;
+
+    let mut addresses: Vec<Ipv4Addr> = endpoint
+        .ipv4_addresses
+        .iter()
+        .flatten()
+        .filter_map(|address| address.trim().parse().ok())
+        .collect();
+    addresses.sort();
+    addresses.dedup();
+
+    Ok(futures::future::join_all(addresses.into_iter().map(reverse_lookup)).await)
+}
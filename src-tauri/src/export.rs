@@ -0,0 +1,299 @@
+use rust_xlsxwriter::{Format, Workbook, Worksheet, XlsxError};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::stats::compute_endpoint_stats;
+use crate::{
+    cache_file, fetch_with_saved_credentials, get_cache_path, read_cache_file, require_credentials, SophosEndpoint,
+};
+
+/// Column layout shared by every tabular export.
+pub(crate) const ENDPOINT_COLUMNS: &[&str] = &[
+    "ID",
+    "Hostname",
+    "Type",
+    "Online",
+    "OS",
+    "Health",
+    "Group",
+    "IPv4 Addresses",
+    "IPv6 Addresses",
+    "Last Seen",
+];
+
+pub(crate) fn endpoint_row(endpoint: &SophosEndpoint) -> Vec<String> {
+    let online = match endpoint.online {
+        Some(true) => "yes",
+        Some(false) => "no",
+        None => "unknown",
+    };
+
+    vec![
+        endpoint.id.clone(),
+        endpoint.hostname.clone().unwrap_or_default(),
+        endpoint.endpoint_type.as_ref().map(|t| t.to_string()).unwrap_or_default(),
+        online.to_string(),
+        endpoint.os_name(),
+        endpoint.health_status(),
+        endpoint.group_name().unwrap_or_default(),
+        endpoint.ipv4_addresses.clone().unwrap_or_default().join("; "),
+        endpoint.ipv6_addresses.clone().unwrap_or_default().join("; "),
+        endpoint.last_seen.clone().unwrap_or_default(),
+    ]
+}
+
+/// Quotes a CSV field when it contains a delimiter, quote or line break (RFC 4180).
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Writes endpoints as CSV with the shared column layout, returning the number of rows.
+pub(crate) fn write_endpoints_csv(endpoints: &[SophosEndpoint], path: &str) -> Result<usize, String> {
+    let mut lines = Vec::with_capacity(endpoints.len() + 1);
+    lines.push(ENDPOINT_COLUMNS.iter().map(|column| csv_field(column)).collect::<Vec<_>>().join(","));
+    for endpoint in endpoints {
+        lines.push(endpoint_row(endpoint).iter().map(|value| csv_field(value)).collect::<Vec<_>>().join(","));
+    }
+
+    std::fs::write(path, format!("{}\r\n", lines.join("\r\n")))
+        .map_err(|e| format!("Failed to write CSV export: {}", e))?;
+    Ok(endpoints.len())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct XlsxExportResult {
+    path: String,
+    sheet_count: usize,
+    endpoint_rows: usize,
+    summary_rows: usize,
+}
+
+fn write_header(worksheet: &mut Worksheet, headers: &[&str], format: &Format) -> Result<(), XlsxError> {
+    for (col, header) in headers.iter().enumerate() {
+        worksheet.write_string_with_format(0, col as u16, *header, format)?;
+    }
+    worksheet.set_freeze_panes(1, 0)?;
+    Ok(())
+}
+
+/// Writes a titled block of counts, returning the next free row.
+fn write_count_section(
+    worksheet: &mut Worksheet,
+    mut row: u32,
+    title: &str,
+    counts: &BTreeMap<String, usize>,
+    title_format: &Format,
+) -> Result<u32, XlsxError> {
+    worksheet.write_string_with_format(row, 0, title, title_format)?;
+    row += 1;
+    for (name, count) in counts {
+        worksheet.write_string(row, 0, name)?;
+        worksheet.write_number(row, 1, *count as f64)?;
+        row += 1;
+    }
+    Ok(row + 1)
+}
+
+fn write_endpoints_workbook(endpoints: &[SophosEndpoint], path: &str) -> Result<(usize, usize), XlsxError> {
+    let bold = Format::new().set_bold();
+    let mut workbook = Workbook::new();
+
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name("Endpoints")?;
+    write_header(worksheet, ENDPOINT_COLUMNS, &bold)?;
+    for (index, endpoint) in endpoints.iter().enumerate() {
+        for (col, value) in endpoint_row(endpoint).iter().enumerate() {
+            worksheet.write_string(index as u32 + 1, col as u16, value)?;
+        }
+    }
+    worksheet.autofit();
+
+    let stats = compute_endpoint_stats(endpoints);
+    let summary = workbook.add_worksheet();
+    summary.set_name("Summary")?;
+    write_header(summary, &["Metric", "Count"], &bold)?;
+    summary.write_string(1, 0, "Total")?;
+    summary.write_number(1, 1, stats.total as f64)?;
+    summary.write_string(2, 0, "Online")?;
+    summary.write_number(2, 1, stats.online as f64)?;
+    summary.write_string(3, 0, "Offline")?;
+    summary.write_number(3, 1, stats.offline as f64)?;
+
+    let mut row = 5;
+    row = write_count_section(summary, row, "By OS", &stats.by_os, &bold)?;
+    row = write_count_section(summary, row, "By Health", &stats.by_health, &bold)?;
+    write_count_section(summary, row, "By Type", &stats.by_type, &bold)?;
+    summary.autofit();
+
+    workbook.save(path)?;
+
+    let summary_rows = 3 + stats.by_os.len() + stats.by_health.len() + stats.by_type.len();
+    Ok((endpoints.len(), summary_rows))
+}
+
+#[tauri::command]
+pub(crate) async fn export_endpoints_xlsx(
+    access_token: String,
+    path: String,
+) -> Result<XlsxExportResult, String> {
+    let endpoints = fetch_with_saved_credentials(access_token).await?;
+
+    let (endpoint_rows, summary_rows) = write_endpoints_workbook(&endpoints, &path)
+        .map_err(|e| format!("Failed to write Excel export: {}", e))?;
+
+    println!("📊 Exported {} endpoints to {}", endpoint_rows, path);
+
+    Ok(XlsxExportResult {
+        path,
+        sheet_count: 2,
+        endpoint_rows,
+        summary_rows,
+    })
+}
+
+#[derive(Debug, Serialize)]
+struct JsonExportHeader<'a> {
+    tenant_id: &'a str,
+    exported_at: String,
+    count: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct JsonExport<'a> {
+    header: JsonExportHeader<'a>,
+    endpoints: &'a [SophosEndpoint],
+}
+
+fn write_endpoints_json(tenant_id: &str, endpoints: &[SophosEndpoint], path: &str, pretty: bool) -> Result<(), String> {
+    let export = JsonExport {
+        header: JsonExportHeader {
+            tenant_id,
+            exported_at: chrono::Utc::now().to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            count: endpoints.len(),
+        },
+        endpoints,
+    };
+
+    let json_content = if pretty {
+        serde_json::to_string_pretty(&export)
+    } else {
+        serde_json::to_string(&export)
+    }
+    .map_err(|e| format!("Failed to serialize export: {}", e))?;
+
+    std::fs::write(path, json_content).map_err(|e| format!("Failed to write JSON export: {}", e))
+}
+
+/// Writes the cached inventory to `path` as JSON. Cache-only: there is no token here to
+/// fetch with, so an empty cache is an error rather than a silent empty file.
+#[tauri::command]
+pub(crate) async fn export_endpoints_json(path: String, pretty: bool) -> Result<String, String> {
+    let cached_data = read_cache_file(&get_cache_path())?;
+    write_endpoints_json(&cached_data.tenant_id, &cached_data.endpoints, &path, pretty)?;
+
+    println!("📄 Exported {} endpoints to {}", cached_data.endpoints.len(), path);
+    Ok(path)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum ExportFormat {
+    Csv,
+    Json,
+    Xlsx,
+}
+
+impl ExportFormat {
+    fn parse(format: &str) -> Result<Self, String> {
+        match format.trim().to_lowercase().as_str() {
+            "csv" => Ok(ExportFormat::Csv),
+            "json" => Ok(ExportFormat::Json),
+            "xlsx" => Ok(ExportFormat::Xlsx),
+            other => Err(format!("Unsupported export format '{}'. Use csv, json or xlsx.", other)),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct ExportSummary {
+    endpoint_count: usize,
+    bytes_written: u64,
+    format: ExportFormat,
+    path: String,
+}
+
+/// Fetches the inventory and writes it to `path` in one call, for scheduled or scripted
+/// exports. `force_refresh` drops the cached inventory first so the export is fresh from
+/// Sophos. The format is checked before anything is fetched.
+#[tauri::command]
+pub(crate) async fn fetch_and_export(
+    access_token: String,
+    format: String,
+    path: String,
+    force_refresh: bool,
+) -> Result<ExportSummary, String> {
+    let format = ExportFormat::parse(&format)?;
+    let credentials = require_credentials()?;
+
+    if force_refresh {
+        cache_file::remove(&get_cache_path()).map_err(|e| format!("Failed to clear cache: {}", e))?;
+    }
+    let endpoints = fetch_with_saved_credentials(access_token).await?;
+
+    match format {
+        ExportFormat::Csv => {
+            write_endpoints_csv(&endpoints, &path)?;
+        }
+        ExportFormat::Json => write_endpoints_json(&credentials.tenant_id, &endpoints, &path, true)?,
+        ExportFormat::Xlsx => {
+            write_endpoints_workbook(&endpoints, &path).map_err(|e| format!("Failed to write Excel export: {}", e))?;
+        }
+    }
+
+    let bytes_written = std::fs::metadata(&path)
+        .map(|metadata| metadata.len())
+        .map_err(|e| format!("Export written but could not be read back: {}", e))?;
+
+    println!("📦 Fetched and exported {} endpoints to {} ({} bytes)", endpoints.len(), path, bytes_written);
+    Ok(ExportSummary {
+        endpoint_count: endpoints.len(),
+        bytes_written,
+        format,
+        path,
+    })
+}
+
+/// Writes the saved region/tenant as a credentials file with the secret parts left blank,
+/// for sharing a setup with another machine. The `_`-prefixed fields are explanations
+/// only; loading the file ignores them.
+#[tauri::command]
+pub(crate) async fn export_config_template(path: String) -> Result<String, String> {
+    let credentials = require_credentials()?;
+
+    let template = serde_json::json!({
+        "_instructions": "Fill in client_id and client_secret with your own Sophos Central API credentials (Global Settings > API Credentials Management), then import this file.",
+        "_fields": {
+            "client_id": "API client ID (required, fill in)",
+            "client_secret": "API client secret (required, fill in, keep private)",
+            "tenant_id": "Sophos Central tenant ID (pre-filled)",
+            "region": "Sophos data region such as us01 or eu01 (pre-filled)",
+            "allow_unknown_region": "Set true only for a region this app doesn't recognize yet"
+        },
+        "client_id": "",
+        "client_secret": "",
+        "tenant_id": credentials.tenant_id,
+        "region": credentials.region,
+        "allow_unknown_region": credentials.allow_unknown_region,
+    });
+
+    let json_content = serde_json::to_string_pretty(&template)
+        .map_err(|e| format!("Failed to serialize template: {}", e))?;
+    std::fs::write(&path, json_content).map_err(|e| format!("Failed to write config template: {}", e))?;
+
+    println!("📄 Config template written to {}", path);
+    Ok(path)
+}
@@ -0,0 +1,179 @@
+use chrono::{DateTime, Utc};
+
+use crate::api_host::resolve_endpoints_base_url;
+use crate::settings::load_settings;
+use crate::{
+    apply_dedup_strategy, fetch_endpoint_pages, get_cache_path, http_client, load_cached_data,
+    load_cached_endpoints, require_credentials, save_cached_data, CacheWriteMode, SophosEndpoint, SophosError,
+    TENANT_CACHE_PREFIX,
+};
+
+fn parse_rfc3339(value: &str, name: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(value.trim())
+        .map(|timestamp| timestamp.with_timezone(&Utc))
+        .map_err(|e| format!("Invalid {} '{}': expected RFC 3339 (e.g. 2024-05-01T00:00:00Z): {}", name, value, e))
+}
+
+/// Fallback for a server-side filter Sophos rejected: a fresh, unfiltered fetch narrowed
+/// locally. Bypasses the cache on purpose, since callers want current data.
+async fn fetch_all_matching(
+    client: &reqwest::Client,
+    base_url: &str,
+    access_token: &str,
+    tenant_id: &str,
+    keep: impl Fn(&SophosEndpoint) -> bool,
+) -> Result<Vec<SophosEndpoint>, String> {
+    let endpoints = fetch_endpoint_pages(
+        client,
+        base_url,
+        access_token,
+        tenant_id,
+        &[],
+        load_settings().dedup_strategy,
+    )
+    .await?;
+    Ok(endpoints.into_iter().filter(|endpoint| keep(endpoint)).collect())
+}
+
+/// Endpoints seen at or after `since` (RFC 3339), for point-in-time audit queries. Uses
+/// the `lastSeenAfter` filter, or filters a fresh full fetch locally when Sophos rejects
+/// it. Never touches the cache.
+#[tauri::command]
+pub(crate) async fn fetch_endpoints_since(access_token: String, since: String) -> Result<Vec<SophosEndpoint>, String> {
+    let since = parse_rfc3339(&since, "since")?;
+    let credentials = require_credentials()?;
+    let client = http_client()?;
+    let base_url = resolve_endpoints_base_url(&client, &access_token, &credentials).await?;
+    let query = [(
+        "lastSeenAfter",
+        since.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+    )];
+
+    match fetch_endpoint_pages(
+        &client,
+        &base_url,
+        &access_token,
+        &credentials.tenant_id,
+        &query,
+        load_settings().dedup_strategy,
+    )
+    .await
+    {
+        Ok(endpoints) => {
+            println!("🕒 {} endpoints seen since {}", endpoints.len(), since);
+            Ok(endpoints)
+        }
+        Err(SophosError::Api { status: 400, .. }) => {
+            println!("⚠️  lastSeenAfter not supported, filtering the full inventory locally");
+            fetch_all_matching(&client, &base_url, &access_token, &credentials.tenant_id, |endpoint| {
+                endpoint.last_seen_at().is_some_and(|last_seen| last_seen >= since)
+            })
+            .await
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Cached endpoints last seen before `iso_timestamp` (RFC 3339), e.g. the start of an
+/// outage, oldest first. Endpoints with no parseable `lastSeen` are included and come first.
+#[tauri::command]
+pub(crate) async fn find_endpoints_last_seen_before(iso_timestamp: String) -> Result<Vec<SophosEndpoint>, String> {
+    let cutoff = parse_rfc3339(&iso_timestamp, "timestamp")?;
+
+    let mut endpoints: Vec<(Option<DateTime<Utc>>, SophosEndpoint)> = load_cached_endpoints()?
+        .into_iter()
+        .map(|endpoint| (endpoint.last_seen_at(), endpoint))
+        .filter(|(last_seen, _)| last_seen.is_none_or(|last_seen| last_seen < cutoff))
+        .collect();
+    endpoints.sort_by_key(|(last_seen, _)| *last_seen);
+
+    Ok(endpoints.into_iter().map(|(_, endpoint)| endpoint).collect())
+}
+
+/// Current members of an endpoint group, for drilling down from the group distribution.
+/// Asks Sophos with the `groupId` filter, since cached membership may be stale, and only
+/// filters a fresh full fetch by `group.id` when the filter is rejected. Never touches
+/// the cache.
+#[tauri::command]
+pub(crate) async fn fetch_endpoints_in_group(
+    access_token: String,
+    group_id: String,
+) -> Result<Vec<SophosEndpoint>, String> {
+    let group_id = group_id.trim().to_string();
+    if group_id.is_empty() {
+        return Err("Group id must not be empty".to_string());
+    }
+
+    let credentials = require_credentials()?;
+    let client = http_client()?;
+    let base_url = resolve_endpoints_base_url(&client, &access_token, &credentials).await?;
+    let query = [("groupId", group_id.clone())];
+
+    match fetch_endpoint_pages(
+        &client,
+        &base_url,
+        &access_token,
+        &credentials.tenant_id,
+        &query,
+        load_settings().dedup_strategy,
+    )
+    .await
+    {
+        Ok(endpoints) => {
+            println!("👥 {} endpoints in group {}", endpoints.len(), group_id);
+            Ok(endpoints)
+        }
+        Err(SophosError::Api { status: 400, .. }) => {
+            println!("⚠️  groupId filter not supported, filtering the full inventory locally");
+            fetch_all_matching(&client, &base_url, &access_token, &credentials.tenant_id, |endpoint| {
+                endpoint.group_id().as_deref() == Some(group_id.as_str())
+            })
+            .await
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Health states the triage view cares about.
+const UNHEALTHY_STATUSES: &[&str] = &["suspicious", "bad"];
+
+/// Only suspicious and bad endpoints, filtered server-side so the healthy majority is
+/// never downloaded. Cached separately from the full inventory.
+#[tauri::command]
+pub(crate) async fn fetch_unhealthy_endpoints(access_token: String) -> Result<Vec<SophosEndpoint>, String> {
+    let credentials = require_credentials()?;
+    let dedup_strategy = load_settings().dedup_strategy;
+    let cache_path = get_cache_path().with_file_name(format!(
+        "{}_health-{}.json",
+        TENANT_CACHE_PREFIX,
+        UNHEALTHY_STATUSES.join("-")
+    ));
+
+    if let Some(cached_endpoints) = load_cached_data(&cache_path, &credentials.tenant_id) {
+        return Ok(apply_dedup_strategy(cached_endpoints, dedup_strategy));
+    }
+
+    let client = http_client()?;
+    let base_url = resolve_endpoints_base_url(&client, &access_token, &credentials).await?;
+    // Sophos ORs repeated healthStatus parameters
+    let query: Vec<(&str, String)> = UNHEALTHY_STATUSES
+        .iter()
+        .map(|status| ("healthStatus", status.to_string()))
+        .collect();
+
+    let endpoints = fetch_endpoint_pages(
+        &client,
+        &base_url,
+        &access_token,
+        &credentials.tenant_id,
+        &query,
+        dedup_strategy,
+    )
+    .await?;
+
+    println!("🩹 {} unhealthy endpoints", endpoints.len());
+    // This file only ever holds the unhealthy subset, so it is replaced, not merged
+    save_cached_data(&cache_path, &endpoints, &credentials.tenant_id, CacheWriteMode::Replace);
+
+    Ok(apply_dedup_strategy(endpoints, dedup_strategy))
+}
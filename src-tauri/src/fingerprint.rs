@@ -0,0 +1,58 @@
+use sha2::{Digest, Sha256};
+
+use crate::{load_cached_endpoints, SophosEndpoint};
+
+/// Bumped whenever the inputs below change, so old and new fingerprints never collide.
+const FINGERPRINT_VERSION: &str = "v1";
+
+/// Short hostname, trimmed and lowercased: `WS-042.corp.example.com` becomes `ws-042`.
+fn normalized_hostname(endpoint: &SophosEndpoint) -> String {
+    endpoint
+        .hostname
+        .as_deref()
+        .map(str::trim)
+        .and_then(|hostname| hostname.split('.').next())
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+fn os_platform(endpoint: &SophosEndpoint) -> String {
+    endpoint
+        .os
+        .as_ref()
+        .and_then(|os| os.get("platform"))
+        .and_then(|value| value.as_str())
+        .unwrap_or_default()
+        .trim()
+        .to_lowercase()
+}
+
+/// SHA-256 over the normalized hostname, primary IPv4 address and OS platform. Sophos
+/// doesn't expose MAC addresses on the endpoint list, so they aren't part of it. Missing
+/// fields hash as empty strings, and every field is labelled so `("a", "")` and `("", "a")`
+/// can't produce the same input.
+fn endpoint_fingerprint(endpoint: &SophosEndpoint) -> String {
+    let input = format!(
+        "{}\nhostname={}\nipv4={}\nplatform={}",
+        FINGERPRINT_VERSION,
+        normalized_hostname(endpoint),
+        endpoint.primary_ipv4().map(|ip| ip.to_string()).unwrap_or_default(),
+        os_platform(endpoint)
+    );
+
+    Sha256::digest(input.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Stable hex fingerprint for matching a cached endpoint against other inventories.
+#[tauri::command]
+pub(crate) async fn get_endpoint_fingerprint(endpoint_id: String) -> Result<String, String> {
+    let endpoint = load_cached_endpoints()?
+        .into_iter()
+        .find(|endpoint| endpoint.id == endpoint_id)
+        .ok_or_else(|| format!("Endpoint {} is not in the cache", endpoint_id))?;
+
+    Ok(endpoint_fingerprint(&endpoint))
+}
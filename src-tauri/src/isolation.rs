@@ -0,0 +1,310 @@
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::api_host::resolve_endpoints_base_url;
+use crate::actions::BULK_ACTION_CONCURRENCY;
+use crate::client::SophosClient;
+use crate::settings::load_settings;
+use crate::views::view_endpoints;
+use crate::{
+    describe_api_error, fetch_endpoint_detail, fetch_endpoint_pages, fetch_with_saved_credentials, http_client,
+    require_credentials, validate_region, SophosEndpoint, SophosError,
+};
+
+/// Isolation rarely changes, so a short-lived result spares repeated full scans.
+const ISOLATION_CACHE_TTL: Duration = Duration::from_secs(120);
+
+/// Upper bound on simultaneous per-endpoint detail requests in the fallback path.
+const ISOLATION_CHECK_CONCURRENCY: usize = 5;
+
+struct IsolationCache {
+    tenant_id: String,
+    fetched_at: Instant,
+    endpoints: Vec<SophosEndpoint>,
+}
+
+static ISOLATION_CACHE: Mutex<Option<IsolationCache>> = Mutex::new(None);
+
+const ISOLATION_UNSUPPORTED: &str = "Endpoint isolation is not available for this tenant's license";
+
+fn cached_isolated(tenant_id: &str) -> Option<Vec<SophosEndpoint>> {
+    let cache = ISOLATION_CACHE.lock().ok()?;
+    cache
+        .as_ref()
+        .filter(|cache| cache.tenant_id == tenant_id && cache.fetched_at.elapsed() < ISOLATION_CACHE_TTL)
+        .map(|cache| cache.endpoints.clone())
+}
+
+fn store_isolated(tenant_id: &str, endpoints: &[SophosEndpoint]) {
+    if let Ok(mut cache) = ISOLATION_CACHE.lock() {
+        *cache = Some(IsolationCache {
+            tenant_id: tenant_id.to_string(),
+            fetched_at: Instant::now(),
+            endpoints: endpoints.to_vec(),
+        });
+    }
+}
+
+/// Reads `isolation.status` from an endpoint detail; `None` when the tenant reports no
+/// isolation block at all.
+fn isolation_status(detail: &serde_json::Value) -> Option<&str> {
+    detail
+        .get("isolation")
+        .map(|isolation| isolation.get("status").and_then(|status| status.as_str()).unwrap_or("notIsolated"))
+}
+
+/// Returns endpoints that are currently isolated. Uses the `isolationStatus` list filter,
+/// and checks each endpoint's detail when the API rejects that filter.
+#[tauri::command]
+pub(crate) async fn get_isolated_endpoints(access_token: String) -> Result<Vec<SophosEndpoint>, String> {
+    let credentials = require_credentials()?;
+
+    if let Some(endpoints) = cached_isolated(&credentials.tenant_id) {
+        println!("📦 Using cached isolation status ({} isolated)", endpoints.len());
+        return Ok(endpoints);
+    }
+
+    let client = http_client()?;
+    let base_url = resolve_endpoints_base_url(&client, &access_token, &credentials).await?;
+    let query = [("isolationStatus", "isolated".to_string())];
+
+    let isolated = match fetch_endpoint_pages(
+        &client,
+        &base_url,
+        &access_token,
+        &credentials.tenant_id,
+        &query,
+        load_settings().dedup_strategy,
+    )
+    .await
+    {
+        Ok(endpoints) => endpoints,
+        Err(SophosError::Api { status: 403, .. }) => return Err(ISOLATION_UNSUPPORTED.to_string()),
+        Err(SophosError::Api { status: 400, .. }) => {
+            println!("⚠️  isolationStatus filter not supported, checking endpoints individually");
+
+            let endpoints = fetch_with_saved_credentials(access_token.clone()).await?;
+            let total = endpoints.len();
+
+            let mut results = stream::iter(endpoints)
+                .map(|endpoint| {
+                    let client = client.clone();
+                    let base_url = base_url.clone();
+                    let access_token = access_token.clone();
+                    let tenant_id = credentials.tenant_id.clone();
+                    async move {
+                        let detail =
+                            fetch_endpoint_detail(&client, &base_url, &access_token, &tenant_id, &endpoint.id).await;
+                        (endpoint, detail)
+                    }
+                })
+                .buffer_unordered(ISOLATION_CHECK_CONCURRENCY);
+
+            let mut isolated = Vec::new();
+            let mut reported = 0;
+            while let Some((endpoint, detail)) = results.next().await {
+                match detail {
+                    Ok(detail) => {
+                        if let Some(status) = isolation_status(&detail) {
+                            reported += 1;
+                            if status == "isolated" {
+                                isolated.push(endpoint);
+                            }
+                        }
+                    }
+                    Err(e) => println!("   ⚠️  Skipping endpoint {}: {}", endpoint.id, e),
+                }
+            }
+
+            if total > 0 && reported == 0 {
+                return Err(ISOLATION_UNSUPPORTED.to_string());
+            }
+            isolated
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    println!("🔒 {} endpoints are isolated", isolated.len());
+    store_isolated(&credentials.tenant_id, &isolated);
+    Ok(isolated)
+}
+
+/// Turns isolation on or off for one endpoint.
+async fn isolation_request(
+    client: &impl SophosClient,
+    base_url: &str,
+    access_token: &str,
+    tenant_id: &str,
+    endpoint_id: &str,
+    enabled: bool,
+) -> Result<(), SophosError> {
+    let response = client
+        .post_action(
+            &format!("{}/{}/isolation", base_url, endpoint_id),
+            access_token,
+            tenant_id,
+            &serde_json::json!({ "enabled": enabled }),
+        )
+        .await
+        .map_err(|e| SophosError::Request(format!("Isolation request failed for {}: {}", endpoint_id, e)))?;
+
+    if !(200..300).contains(&response.status) {
+        return Err(SophosError::Api {
+            status: response.status,
+            message: format!(
+                "Isolation change failed for {} ({}): {}",
+                endpoint_id,
+                response.status,
+                describe_api_error(&response.body)
+            ),
+        });
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct IsolationOutcome {
+    id: String,
+    hostname: Option<String>,
+    success: bool,
+    error: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct BulkIsolationResult {
+    /// True when `confirm` was false and nothing was changed
+    dry_run: bool,
+    enabled: bool,
+    requested: usize,
+    succeeded: usize,
+    failed: usize,
+    /// Offline endpoints left out; isolation can only be applied to a connected machine
+    skipped_offline: usize,
+    results: Vec<IsolationOutcome>,
+}
+
+/// Isolates (`enabled: true`) or releases every cached endpoint matching the saved view.
+/// Without `confirm: true` this only reports which endpoints would change. Offline
+/// endpoints are skipped when isolating.
+#[tauri::command]
+pub(crate) async fn isolate_endpoints_matching(
+    access_token: String,
+    view_name: String,
+    enabled: bool,
+    confirm: bool,
+) -> Result<BulkIsolationResult, String> {
+    let credentials = require_credentials()?;
+    validate_region(&credentials.region, credentials.allow_unknown_region)?;
+    let endpoints = view_endpoints(&view_name)?;
+
+    let total = endpoints.len();
+    let targets: Vec<_> = endpoints
+        .into_iter()
+        .filter(|endpoint| !enabled || endpoint.online == Some(true))
+        .collect();
+    let skipped_offline = total - targets.len();
+    let requested = targets.len();
+    let action = if enabled { "isolate" } else { "release" };
+
+    println!(
+        "🔒 {} endpoints in view '{}' to {} ({} offline skipped)",
+        requested,
+        view_name.trim(),
+        action,
+        skipped_offline
+    );
+
+    if !confirm {
+        let results = targets
+            .into_iter()
+            .map(|endpoint| IsolationOutcome {
+                id: endpoint.id,
+                hostname: endpoint.hostname,
+                success: false,
+                error: None,
+            })
+            .collect();
+        return Ok(BulkIsolationResult {
+            dry_run: true,
+            enabled,
+            requested,
+            succeeded: 0,
+            failed: 0,
+            skipped_offline,
+            results,
+        });
+    }
+
+    let client = http_client()?;
+    let base_url = resolve_endpoints_base_url(&client, &access_token, &credentials).await?;
+
+    let results: Vec<IsolationOutcome> = stream::iter(targets)
+        .map(|endpoint| {
+            let client = client.clone();
+            let base_url = base_url.clone();
+            let access_token = access_token.clone();
+            let tenant_id = credentials.tenant_id.clone();
+            async move {
+                let result =
+                    isolation_request(&client, &base_url, &access_token, &tenant_id, &endpoint.id, enabled).await;
+                IsolationOutcome {
+                    id: endpoint.id,
+                    hostname: endpoint.hostname,
+                    success: result.is_ok(),
+                    error: result.err().map(|e| e.to_string()),
+                }
+            }
+        })
+        .buffer_unordered(BULK_ACTION_CONCURRENCY)
+        .collect()
+        .await;
+
+    let succeeded = results.iter().filter(|outcome| outcome.success).count();
+    if succeeded > 0 {
+        // The cached isolated list is now out of date
+        if let Ok(mut cache) = ISOLATION_CACHE.lock() {
+            *cache = None;
+        }
+    }
+
+    println!(
+        "✅ {} of {} endpoints {}",
+        succeeded,
+        requested,
+        if enabled { "isolated" } else { "released" }
+    );
+    Ok(BulkIsolationResult {
+        dry_run: false,
+        enabled,
+        requested,
+        succeeded,
+        failed: requested - succeeded,
+        skipped_offline,
+        results,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{json_response, status, FakeClient};
+
+    const BASE_URL: &str = "https://api-eu01.central.sophos.com/endpoint/v1/endpoints";
+
+    #[tokio::test]
+    async fn isolation_change_posts_the_requested_state() {
+        let client = FakeClient::with_responses(vec![json_response(serde_json::json!({})), status(400)]);
+
+        isolation_request(&client, BASE_URL, "token", "tenant", "a", false).await.unwrap();
+        let rejected = isolation_request(&client, BASE_URL, "token", "tenant", "b", true).await;
+
+        assert!(matches!(rejected, Err(SophosError::Api { status: 400, .. })));
+        let calls = client.calls();
+        assert_eq!(calls[0].url, format!("{}/a/isolation", BASE_URL));
+        assert_eq!(calls[0].body, Some(serde_json::json!({ "enabled": false })));
+        assert_eq!(calls[1].body, Some(serde_json::json!({ "enabled": true })));
+    }
+}
@@ -1,9 +1,54 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use sha2::{Digest, Sha256};
+use tauri::Manager;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
+use std::sync::OnceLock;
 
 use std::time::{SystemTime, UNIX_EPOCH};
 
+mod actions;
+mod alerts;
+mod api_host;
+mod audit;
+mod backup;
+mod cache_file;
+mod cache_repair;
+mod client;
+mod compliance;
+mod details;
+mod diagnostics;
+mod dns;
+mod export;
+mod filters;
+mod fingerprint;
+mod isolation;
+mod migrations;
+mod paging;
+mod partner;
+mod policies;
+mod queries;
+mod rate_limit;
+mod services;
+mod settings;
+mod snapshots;
+mod stats;
+mod streaming;
+mod summary;
+mod tenant;
+#[cfg(test)]
+mod test_support;
+mod timings;
+mod token;
+mod ui_state;
+mod updates;
+mod views;
+
+use client::{ClientState, SophosClient};
+use settings::{load_settings, DedupStrategy, PaginationMode};
+use timings::FetchTimer;
+use token::TokenState;
+
 #[derive(Debug, Serialize, Deserialize)]
 struct SophosTokenResponse {
     access_token: String,
@@ -11,13 +56,64 @@ struct SophosTokenResponse {
     expires_in: u64,
 }
 
+/// Sophos endpoint `type`. Values this build doesn't know are kept verbatim so they
+/// survive a cache round-trip.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq)]
+#[serde(from = "String", into = "String")]
+enum EndpointType {
+    Computer,
+    Server,
+    SecurityVm,
+    Mobile,
+    Utm,
+    Unknown(String),
+}
+
+impl EndpointType {
+    fn as_str(&self) -> &str {
+        match self {
+            EndpointType::Computer => "computer",
+            EndpointType::Server => "server",
+            EndpointType::SecurityVm => "securityVm",
+            EndpointType::Mobile => "mobile",
+            EndpointType::Utm => "utm",
+            EndpointType::Unknown(value) => value,
+        }
+    }
+}
+
+impl From<String> for EndpointType {
+    fn from(value: String) -> Self {
+        match value.as_str() {
+            "computer" => EndpointType::Computer,
+            "server" => EndpointType::Server,
+            "securityVm" => EndpointType::SecurityVm,
+            "mobile" => EndpointType::Mobile,
+            "utm" => EndpointType::Utm,
+            _ => EndpointType::Unknown(value),
+        }
+    }
+}
+
+impl From<EndpointType> for String {
+    fn from(endpoint_type: EndpointType) -> Self {
+        endpoint_type.as_str().to_string()
+    }
+}
+
+impl std::fmt::Display for EndpointType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.as_str())
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 struct SophosEndpoint {
     id: String,
     hostname: Option<String>,
     os: Option<serde_json::Value>,
     #[serde(rename = "type")]
-    endpoint_type: Option<String>,
+    endpoint_type: Option<EndpointType>,
     online: Option<bool>,
     health: Option<serde_json::Value>,
     group: Option<serde_json::Value>,
@@ -31,56 +127,868 @@ struct SophosEndpoint {
     last_seen: Option<String>,
 }
 
+impl SophosEndpoint {
+    /// OS display name, falling back to the platform when Sophos omits the name.
+    fn os_name(&self) -> String {
+        self.os
+            .as_ref()
+            .and_then(|os| os.get("name").or_else(|| os.get("platform")))
+            .and_then(|value| value.as_str())
+            .unwrap_or("Unknown")
+            .to_string()
+    }
+
+    /// Overall health as reported by Sophos (`good`, `suspicious`, `bad`), or `unknown`.
+    fn health_status(&self) -> String {
+        self.health
+            .as_ref()
+            .and_then(|health| health.get("overall"))
+            .and_then(|value| value.as_str())
+            .unwrap_or("unknown")
+            .to_string()
+    }
+
+    /// Ranks overall health for sorting: bad (3), suspicious (2), good (1), unknown (0).
+    fn health_severity(&self) -> u8 {
+        match self.health_status().as_str() {
+            "bad" => 3,
+            "suspicious" => 2,
+            "good" => 1,
+            _ => 0,
+        }
+    }
+
+    /// Every reported address across the three IP fields that parses and is actually
+    /// routable-looking (not loopback or unspecified), deduplicated.
+    fn usable_ip_addresses(&self) -> Vec<std::net::IpAddr> {
+        let mut addresses = Vec::new();
+        let fields = [&self.ip_addresses, &self.ipv4_addresses, &self.ipv6_addresses];
+
+        for address in fields.into_iter().flatten().flatten() {
+            let Ok(ip) = address.trim().parse::<std::net::IpAddr>() else {
+                continue;
+            };
+            if ip.is_loopback() || ip.is_unspecified() || addresses.contains(&ip) {
+                continue;
+            }
+            addresses.push(ip);
+        }
+
+        addresses
+    }
+
+    /// Lowest valid IPv4 address. Sophos doesn't keep address order stable between syncs,
+    /// so "first" would not be deterministic.
+    fn primary_ipv4(&self) -> Option<std::net::Ipv4Addr> {
+        self.ipv4_addresses
+            .iter()
+            .flatten()
+            .filter_map(|address| address.trim().parse::<std::net::Ipv4Addr>().ok())
+            .min()
+    }
+
+    /// Hostname trimmed and lowercased, the key every hostname match and dedup uses.
+    /// `None` when missing or blank.
+    fn hostname_key(&self) -> Option<String> {
+        self.hostname
+            .as_deref()
+            .map(|hostname| hostname.trim().to_lowercase())
+            .filter(|hostname| !hostname.is_empty())
+    }
+
+    /// Parsed `lastSeen`; `None` when missing or not valid RFC3339.
+    fn last_seen_at(&self) -> Option<chrono::DateTime<chrono::Utc>> {
+        self.last_seen
+            .as_deref()
+            .and_then(|last_seen| chrono::DateTime::parse_from_rfc3339(last_seen.trim()).ok())
+            .map(|last_seen| last_seen.with_timezone(&chrono::Utc))
+    }
+
+    /// Group name; some payloads give the group as a bare string instead of an object.
+    fn group_name(&self) -> Option<String> {
+        let group = self.group.as_ref()?;
+        group
+            .as_str()
+            .or_else(|| group.get("name").and_then(|value| value.as_str()))
+            .filter(|name| !name.is_empty())
+            .map(|name| name.to_string())
+    }
+
+    fn group_id(&self) -> Option<String> {
+        self.group
+            .as_ref()
+            .and_then(|group| group.get("id"))
+            .and_then(|value| value.as_str())
+            .filter(|id| !id.is_empty())
+            .map(|id| id.to_string())
+    }
+}
+
+/// A page of the endpoint list. Items stay raw so one malformed record can't fail the page.
 #[derive(Debug, Serialize, Deserialize)]
 struct SophosEndpointsResponse {
-    items: Option<Vec<SophosEndpoint>>,
+    items: Option<Vec<serde_json::Value>>,
     pages: Option<serde_json::Value>,
 }
 
+impl SophosEndpointsResponse {
+    /// Parses each item on its own, returning the endpoints that parsed and how many were skipped.
+    fn take_endpoints(&mut self) -> (Vec<SophosEndpoint>, usize) {
+        let mut endpoints = Vec::new();
+        let mut skipped = 0;
+
+        for item in self.items.take().unwrap_or_default() {
+            let id = item.get("id").and_then(|id| id.as_str()).unwrap_or("<no id>").to_string();
+            match serde_json::from_value::<SophosEndpoint>(item) {
+                Ok(endpoint) => endpoints.push(endpoint),
+                Err(e) => {
+                    log::debug!("Skipping malformed endpoint {}: {}", id, e);
+                    skipped += 1;
+                }
+            }
+        }
+
+        (endpoints, skipped)
+    }
+}
+
+/// Error body Sophos returns on failed API calls.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct SophosApiError {
+    error: Option<String>,
+    message: Option<String>,
+    #[serde(rename = "correlationId")]
+    correlation_id: Option<String>,
+}
+
+impl SophosApiError {
+    fn parse(body: &str) -> Option<Self> {
+        serde_json::from_str::<SophosApiError>(body)
+            .ok()
+            .filter(|parsed| parsed.error.is_some() || parsed.correlation_id.is_some())
+    }
+
+    /// Whether Sophos is saying the tenant isn't served from the host we called.
+    fn is_region_related(&self) -> bool {
+        [&self.error, &self.message].into_iter().flatten().any(|text| {
+            let text = text.to_lowercase();
+            text.contains("region") || text.contains("tenant")
+        })
+    }
+}
+
+/// Turns a failed response body into readable detail, surfacing the Sophos error code and
+/// correlation id (what Sophos support asks for). Falls back to the raw body.
+fn describe_api_error(body: &str) -> String {
+    let Some(api_error) = SophosApiError::parse(body) else {
+        return body.to_string();
+    };
+
+    let mut detail = api_error
+        .message
+        .clone()
+        .or_else(|| api_error.error.clone())
+        .unwrap_or_default();
+    let mut extras = Vec::new();
+    if let Some(error) = &api_error.error {
+        extras.push(format!("error: {}", error));
+    }
+    if let Some(correlation_id) = &api_error.correlation_id {
+        extras.push(format!("correlation id: {}", correlation_id));
+    }
+    detail.push_str(&format!(" ({})", extras.join(", ")));
+
+    detail
+}
+
+/// Failure talking to the Sophos API, kept structured so callers can react to the status.
+#[derive(Debug)]
+enum SophosError {
+    /// The request never got a response (DNS, TLS, connection, timeout)
+    Request(String),
+    /// Sophos answered with a non-success status
+    Api { status: u16, message: String },
+    /// The response body wasn't the shape we expected
+    Parse(String),
+    /// The token was accepted but the tenant's data lives in a different region
+    RegionMismatch { message: String },
+}
+
+impl std::fmt::Display for SophosError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SophosError::Request(message) | SophosError::Parse(message) => write!(f, "{}", message),
+            SophosError::Api { message, .. } => write!(f, "{}", message),
+            SophosError::RegionMismatch { message } => write!(
+                f,
+                "Your token is valid but the region doesn't match this tenant. Run tenant discovery (whoami) to find the tenant's data region and update your credentials. Details: {}",
+                message
+            ),
+        }
+    }
+}
+
+impl From<SophosError> for String {
+    fn from(error: SophosError) -> Self {
+        error.to_string()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct WhoAmIHosts {
+    global: Option<String>,
+    #[serde(rename = "dataRegion")]
+    data_region: Option<String>,
+}
+
+/// Response of `GET /whoami/v1`: who the token belongs to and where its data lives.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct WhoAmI {
+    id: String,
+    #[serde(rename = "idType")]
+    id_type: String,
+    #[serde(rename = "apiHosts")]
+    api_hosts: Option<WhoAmIHosts>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct SophosCredentials {
+    client_id: String,
+    client_secret: String,
+    tenant_id: String,
+    region: String,
+    #[serde(default)]
+    allow_unknown_region: bool,
+    /// Unix timestamp (seconds) when this `client_id` was first saved. Set by
+    /// `save_sophos_credentials`; whatever the caller passes in is ignored.
+    #[serde(default)]
+    credentials_created_at: Option<u64>,
+}
+
+impl SophosCredentials {
+    /// Trims every field and lowercases the region, rejecting required fields left empty.
+    fn normalized(self) -> Result<Self, String> {
+        let credentials = SophosCredentials {
+            client_id: self.client_id.trim().to_string(),
+            client_secret: self.client_secret.trim().to_string(),
+            tenant_id: self.tenant_id.trim().to_string(),
+            region: self.region.trim().to_lowercase(),
+            allow_unknown_region: self.allow_unknown_region,
+            credentials_created_at: self.credentials_created_at,
+        };
+
+        for (field, value) in [
+            ("client_id", &credentials.client_id),
+            ("client_secret", &credentials.client_secret),
+            ("tenant_id", &credentials.tenant_id),
+        ] {
+            if value.is_empty() {
+                return Err(format!("Credential field '{}' must not be empty", field));
+            }
+        }
+
+        Ok(credentials)
+    }
+}
+
+/// On-disk secrets layout: named credential profiles plus the one in use.
+#[derive(Debug, Serialize, Deserialize)]
+struct SecretsFile {
+    schema_version: u32,
+    active_profile: String,
+    profiles: BTreeMap<String, SophosCredentials>,
+}
+
+impl Default for SecretsFile {
+    fn default() -> Self {
+        SecretsFile {
+            schema_version: SECRETS_SCHEMA_VERSION,
+            active_profile: DEFAULT_PROFILE.to_string(),
+            profiles: BTreeMap::new(),
+        }
+    }
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 struct CachedData {
+    // Missing in caches written before versioning was added
+    #[serde(default)]
+    schema_version: u32,
     endpoints: Vec<SophosEndpoint>,
     timestamp: u64,
     tenant_id: String,
+    /// SHA-256 of the serialized endpoint list; missing in caches written before v2
+    #[serde(default)]
+    checksum: Option<String>,
 }
 
+impl CachedData {
+    fn new(endpoints: Vec<SophosEndpoint>, timestamp: u64, tenant_id: String) -> Self {
+        let checksum = Some(endpoints_checksum(&endpoints));
+        CachedData {
+            schema_version: CACHE_SCHEMA_VERSION,
+            endpoints,
+            timestamp,
+            tenant_id,
+            checksum,
+        }
+    }
+
+    /// Whether the endpoints still match the stored checksum, or `None` when the cache
+    /// predates checksums.
+    fn checksum_matches(&self) -> Option<bool> {
+        self.checksum
+            .as_ref()
+            .map(|checksum| *checksum == endpoints_checksum(&self.endpoints))
+    }
+}
+
+fn endpoints_checksum(endpoints: &[SophosEndpoint]) -> String {
+    let serialized = serde_json::to_string(endpoints).unwrap_or_default();
+    Sha256::digest(serialized.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+const USER_AGENT: &str = concat!("shoreagents-sophos/", env!("CARGO_PKG_VERSION"), " (tauri)");
+const WHOAMI_URL: &str = "https://api.central.sophos.com/whoami/v1";
+const TOKEN_URL: &str = "https://id.sophos.com/api/v2/oauth2/token";
 const CACHE_FILE: &str = "sophos_cache.json";
-const CACHE_DURATION_HOURS: u64 = 1; // Cache for 1 hour
+const TENANT_CACHE_PREFIX: &str = "sophos_cache";
+const SECRETS_FILE: &str = "sophos_secrets.json";
+const CACHE_SCHEMA_VERSION: u32 = 2;
+const INCREMENTAL_SYNC_OVERLAP_SECS: u64 = 300;
+const SECRETS_SCHEMA_VERSION: u32 = 2;
+const DEFAULT_PROFILE: &str = "default";
 
-fn get_cache_path() -> std::path::PathBuf {
-    // Create cache in user's data directory
-    let mut path = dirs::data_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
+// Sophos Central data regions, as used in `api-{region}.central.sophos.com`
+const KNOWN_REGIONS: &[&str] = &[
+    "us01", "us02", "us03", "eu01", "eu02", "de01", "de02", "au01", "ca01", "jp01", "in01", "br01",
+];
+
+/// Sophos issuing chain trusted when `pin_sophos_cert` is on, read from the app data dir.
+const PINNED_CHAIN_FILE: &str = "sophos_ca_chain.pem";
+
+/// The base user agent plus the configured suffix, e.g. `shoreagents-sophos/0.1.0 (tauri) (acme-msp)`.
+fn user_agent(settings: &settings::AppSettings) -> String {
+    match settings.user_agent_suffix.as_deref().map(str::trim) {
+        Some(suffix) if !suffix.is_empty() => match settings::validate_user_agent_suffix(suffix) {
+            Ok(_) => format!("{} ({})", USER_AGENT, suffix),
+            Err(e) => {
+                println!("⚠️  Ignoring user agent suffix: {}", e);
+                USER_AGENT.to_string()
+            }
+        },
+        _ => USER_AGENT.to_string(),
+    }
+}
+
+/// Shared client for every outbound Sophos request, so headers and connection pooling
+/// are configured in one place. With `pin_sophos_cert` enabled, only the Sophos chain
+/// is trusted and the system roots are ignored.
+fn http_client() -> Result<reqwest::Client, String> {
+    let settings = load_settings();
+    let user_agent = user_agent(&settings);
+
+    if settings.pin_sophos_cert {
+        return pinned_http_client(user_agent);
+    }
+
+    let mut cached = client_state()?.plain.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some((built_for, client)) = cached.as_ref() {
+        if *built_for == user_agent {
+            return Ok(client.clone());
+        }
+    }
+
+    let client = reqwest::Client::builder()
+        .user_agent(&user_agent)
+        .build()
+        .unwrap_or_else(|e| {
+            println!("❌ Failed to build HTTP client, using defaults: {}", e);
+            reqwest::Client::new()
+        });
+    *cached = Some((user_agent, client.clone()));
+    Ok(client)
+}
+
+fn pinned_http_client(user_agent: String) -> Result<reqwest::Client, String> {
+    let mut cached = client_state()?.pinned.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    if let Some((built_for, client)) = cached.as_ref() {
+        if *built_for == user_agent {
+            return Ok(client.clone());
+        }
+    }
+
+    // A failed build is not cached, so dropping the chain file in place works without a restart
+    let chain_path = get_app_data_dir().join(PINNED_CHAIN_FILE);
+    let pem = fs::read(&chain_path).map_err(|e| {
+        format!(
+            "Certificate pinning is enabled but the Sophos CA chain could not be read from {}: {}",
+            chain_path.display(),
+            e
+        )
+    })?;
+    let certificates = reqwest::Certificate::from_pem_bundle(&pem)
+        .map_err(|e| format!("Invalid Sophos CA chain in {}: {}", chain_path.display(), e))?;
+    if certificates.is_empty() {
+        return Err(format!("No certificates found in {}", chain_path.display()));
+    }
+
+    let mut builder = reqwest::Client::builder()
+        .user_agent(&user_agent)
+        .tls_built_in_root_certs(false);
+    for certificate in certificates {
+        builder = builder.add_root_certificate(certificate);
+    }
+    let client = builder
+        .build()
+        .map_err(|e| format!("Failed to build pinned HTTP client: {}", e))?;
+
+    println!("🔒 Certificate pinning enabled for Sophos requests");
+    *cached = Some((user_agent, client.clone()));
+    Ok(client)
+}
+
+/// Formats a transport error, pointing at pinning when it is the likely reason the
+/// connection was refused.
+fn describe_request_error(error: &reqwest::Error) -> String {
+    if error.is_connect() && load_settings().pin_sophos_cert {
+        format!(
+            "{} (certificate pinning is enabled: the server certificate may not chain to the pinned Sophos CA)",
+            error
+        )
+    } else {
+        error.to_string()
+    }
+}
+
+static APP_HANDLE: OnceLock<tauri::AppHandle> = OnceLock::new();
+
+/// Handle for code paths that aren't invoked with one (cache writes, pagination).
+/// Set once during setup.
+fn app_handle() -> Option<&'static tauri::AppHandle> {
+    APP_HANDLE.get()
+}
+
+static DATA_DIR_FALLBACK_WARNING: std::sync::Once = std::sync::Once::new();
+
+/// True when the OS reports no user data directory and app files go to the temp dir.
+fn app_data_dir_is_fallback() -> bool {
+    #[cfg(test)]
+    if test_support::data_dir().is_some() {
+        return false;
+    }
+
+    dirs::data_dir().is_none()
+}
+
+fn get_app_data_dir() -> std::path::PathBuf {
+    #[cfg(test)]
+    if let Some(dir) = test_support::data_dir() {
+        return dir;
+    }
+
+    // Keep app files in user's data directory. Without one, use the temp dir rather than
+    // the current working directory, and say so once.
+    let mut path = dirs::data_dir().unwrap_or_else(|| {
+        DATA_DIR_FALLBACK_WARNING.call_once(|| {
+            println!(
+                "⚠️  No user data directory available, storing app data in {}",
+                std::env::temp_dir().display()
+            );
+        });
+        std::env::temp_dir()
+    });
     path.push("sophos-dashboard");
     std::fs::create_dir_all(&path).ok();
-    path.push(CACHE_FILE);
     path
 }
 
+fn get_cache_path() -> std::path::PathBuf {
+    get_app_data_dir().join(CACHE_FILE)
+}
+
+/// A tenant id reduced to characters that are safe in a file name.
+fn safe_tenant_id(tenant_id: &str) -> String {
+    tenant_id
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect()
+}
+
+/// Cache file for a tenant queried via override, kept apart from the default tenant's cache.
+fn get_tenant_cache_path(tenant_id: &str) -> std::path::PathBuf {
+    get_app_data_dir().join(format!("{}_{}.json", TENANT_CACHE_PREFIX, safe_tenant_id(tenant_id)))
+}
+
+/// Normalizes a `fields` selection: sorted, deduplicated, and always including `id`
+/// (every endpoint record needs it).
+fn normalize_fields(fields: &[String]) -> Vec<String> {
+    let mut normalized: Vec<String> = fields
+        .iter()
+        .map(|field| field.trim().to_string())
+        .filter(|field| !field.is_empty())
+        .collect();
+    normalized.push("id".to_string());
+    normalized.sort();
+    normalized.dedup();
+    normalized
+}
+
+/// Cache file for a slimmed (`fields`) fetch, alongside the full-inventory cache it
+/// must not overwrite, e.g. `sophos_cache_fields-health-hostname-id.json`.
+fn fields_cache_path(cache_path: &std::path::Path, fields: &[String]) -> std::path::PathBuf {
+    let stem = cache_path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_else(|| TENANT_CACHE_PREFIX.to_string());
+    let key: String = fields
+        .join("-")
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-')
+        .collect();
+    cache_path.with_file_name(format!("{}_fields-{}.json", stem, key))
+}
+
+/// The OAuth token URL: the `token_endpoint_override` setting when set and valid,
+/// otherwise the Sophos identity host.
+fn token_url() -> String {
+    let Some(url) = load_settings()
+        .token_endpoint_override
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+    else {
+        return TOKEN_URL.to_string();
+    };
+
+    match settings::validate_token_endpoint(&url) {
+        Ok(()) => url,
+        Err(e) => {
+            println!("⚠️  Ignoring token_endpoint_override: {}", e);
+            TOKEN_URL.to_string()
+        }
+    }
+}
+
+/// Root URL of the tenant's API, from an explicit host (e.g. whoami's `apiHost`) or the region.
+fn api_host_url(region: &str, api_host: Option<&str>) -> String {
+    match api_host {
+        Some(host) => {
+            let host = host.trim().trim_end_matches('/');
+            if host.starts_with("https://") || host.starts_with("http://") {
+                host.to_string()
+            } else {
+                format!("https://{}", host)
+            }
+        }
+        None => format!("https://api-{}.central.sophos.com", region),
+    }
+}
+
+fn endpoints_base_url(region: &str, api_host: Option<&str>) -> String {
+    format!("{}/endpoint/v1/endpoints", api_host_url(region, api_host))
+}
+
+/// The API host a fetch will use: the override when given, otherwise the region's host.
+/// The region is only validated when it's actually used to build the URL.
+fn resolve_api_host(region: &str, allow_unknown_region: bool, api_host_override: Option<&str>) -> Result<String, String> {
+    if api_host_override.is_none() {
+        validate_region(region, allow_unknown_region)?;
+    }
+    Ok(api_host_url(region, api_host_override))
+}
+
+/// The API host `fetch_sophos_endpoints` will hit with the saved credentials: the cached
+/// whoami host when there is one, otherwise the region's host.
+#[tauri::command]
+async fn get_effective_api_host() -> Result<String, String> {
+    let credentials = require_credentials()?;
+    let cached_host = api_host::cached_api_host(&credentials.tenant_id);
+    resolve_api_host(&credentials.region, credentials.allow_unknown_region, cached_host.as_deref())
+}
+
+fn get_secrets_path() -> std::path::PathBuf {
+    get_app_data_dir().join(SECRETS_FILE)
+}
+
+/// Checks a region against the known Sophos data regions.
+///
+/// `allow_unknown` lets a region outside the list through (for regions Sophos adds
+/// later), but it must still be a plain host label so it can't break the API URL.
+fn validate_region(region: &str, allow_unknown: bool) -> Result<(), String> {
+    if KNOWN_REGIONS.contains(&region) {
+        return Ok(());
+    }
+
+    if region.is_empty() || !region.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(format!(
+            "Invalid Sophos region '{}': expected a region code like 'us01'. Valid regions: {}",
+            region,
+            KNOWN_REGIONS.join(", ")
+        ));
+    }
+
+    if allow_unknown {
+        println!("⚠️  Using unrecognized region '{}' (allowed by override)", region);
+        return Ok(());
+    }
+
+    Err(format!(
+        "Unrecognized Sophos region '{}'. Valid regions: {}. If this is a newly added Sophos region, enable allow_unknown_region.",
+        region,
+        KNOWN_REGIONS.join(", ")
+    ))
+}
+
+/// Reads the secrets file, upgrading older layouts in place.
+fn load_secrets_file() -> Option<SecretsFile> {
+    let secrets_path = get_secrets_path();
+
+    if !secrets_path.exists() {
+        return None;
+    }
+
+    let content = match fs::read_to_string(&secrets_path) {
+        Ok(content) => content,
+        Err(e) => {
+            println!("❌ Failed to read credentials: {}", e);
+            return None;
+        }
+    };
+
+    match migrations::parse_secrets(&content) {
+        Ok((secrets, found_version)) => {
+            if found_version < SECRETS_SCHEMA_VERSION {
+                match write_secrets_file(&secrets) {
+                    Ok(_) => println!("🔄 Migrated credentials from schema v{} to v{}", found_version, SECRETS_SCHEMA_VERSION),
+                    Err(e) => println!("❌ Failed to write migrated credentials: {}", e),
+                }
+            }
+            Some(secrets)
+        }
+        Err(e) => {
+            println!("❌ Failed to parse credentials: {}", e);
+            None
+        }
+    }
+}
+
+fn write_secrets_file(secrets: &SecretsFile) -> Result<(), String> {
+    // A shared temp dir is no place for a client secret
+    if app_data_dir_is_fallback() {
+        return Err(format!(
+            "No user data directory is available, refusing to store credentials in {}",
+            get_app_data_dir().display()
+        ));
+    }
+
+    let json_content = serde_json::to_string_pretty(secrets)
+        .map_err(|e| format!("Failed to serialize credentials: {}", e))?;
+
+    fs::write(get_secrets_path(), json_content)
+        .map_err(|e| format!("Failed to save credentials: {}", e))
+}
+
+/// Environment variables that together supply credentials, overriding the secrets file.
+const CREDENTIAL_ENV_VARS: [&str; 4] = ["SOPHOS_CLIENT_ID", "SOPHOS_CLIENT_SECRET", "SOPHOS_TENANT_ID", "SOPHOS_REGION"];
+const ALLOW_UNKNOWN_REGION_ENV_VAR: &str = "SOPHOS_ALLOW_UNKNOWN_REGION";
+
+/// Where the active credentials came from.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+enum CredentialSource {
+    Env,
+    File,
+    None,
+}
+
+/// Credentials from `SOPHOS_CLIENT_ID`, `SOPHOS_CLIENT_SECRET`, `SOPHOS_TENANT_ID` and
+/// `SOPHOS_REGION`. All four must be set and non-empty; a partial set is ignored with a
+/// warning rather than mixed with values from the file.
+fn credentials_from_env() -> Option<SophosCredentials> {
+    credentials_from_vars(|name| std::env::var(name).ok())
+}
+
+/// `credentials_from_env` with `var` looking each variable up.
+fn credentials_from_vars(var: impl Fn(&str) -> Option<String>) -> Option<SophosCredentials> {
+    let values: Vec<Option<String>> = CREDENTIAL_ENV_VARS
+        .iter()
+        .map(|name| var(name).filter(|value| !value.trim().is_empty()))
+        .collect();
+
+    if values.iter().all(Option::is_none) {
+        return None;
+    }
+
+    let missing: Vec<&str> = CREDENTIAL_ENV_VARS
+        .iter()
+        .zip(&values)
+        .filter(|(_, value)| value.is_none())
+        .map(|(name, _)| *name)
+        .collect();
+    if !missing.is_empty() {
+        println!("⚠️  Ignoring credential environment variables; missing {}", missing.join(", "));
+        return None;
+    }
+
+    let mut values = values.into_iter().flatten();
+    let credentials = SophosCredentials {
+        client_id: values.next()?,
+        client_secret: values.next()?,
+        tenant_id: values.next()?,
+        region: values.next()?,
+        allow_unknown_region: var(ALLOW_UNKNOWN_REGION_ENV_VAR)
+            .is_some_and(|value| matches!(value.trim().to_lowercase().as_str(), "1" | "true" | "yes")),
+        credentials_created_at: None,
+    };
+
+    match credentials.normalized() {
+        Ok(credentials) => Some(credentials),
+        Err(e) => {
+            println!("⚠️  Ignoring credential environment variables: {}", e);
+            None
+        }
+    }
+}
+
+/// Active credentials and their source: a complete set of environment variables wins
+/// over the secrets file's active profile.
+fn resolve_credentials() -> (Option<SophosCredentials>, CredentialSource) {
+    resolve_credentials_with(credentials_from_env())
+}
+
+fn resolve_credentials_with(from_env: Option<SophosCredentials>) -> (Option<SophosCredentials>, CredentialSource) {
+    if let Some(credentials) = from_env {
+        return (Some(credentials), CredentialSource::Env);
+    }
+
+    let from_file = load_secrets_file().and_then(|secrets| secrets.profiles.get(&secrets.active_profile).cloned());
+    match from_file {
+        Some(credentials) => (Some(credentials), CredentialSource::File),
+        None => (None, CredentialSource::None),
+    }
+}
+
+fn load_credentials() -> Option<SophosCredentials> {
+    resolve_credentials().0
+}
+
+#[tauri::command]
+async fn credentials_source() -> Result<CredentialSource, String> {
+    Ok(resolve_credentials().1)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CredentialsAge {
+    client_id: String,
+    /// Unix timestamp (seconds) when the client id was first saved
+    created_at: u64,
+    age_days: u64,
+    /// The `credentials_rotation_warning_days` setting; 0 disables the warning
+    warn_after_days: u64,
+    rotation_recommended: bool,
+}
+
+/// How long the saved client id has been in use. Sophos doesn't expose credential
+/// expiry, so this only tracks age against the `credentials_rotation_warning_days`
+/// setting. Returns `None` for environment credentials, which aren't tracked.
+/// Credentials saved before tracking was added are dated from the first call.
+#[tauri::command]
+async fn get_credentials_age() -> Result<Option<CredentialsAge>, String> {
+    if credentials_from_env().is_some() {
+        return Ok(None);
+    }
+    let Some(mut secrets) = load_secrets_file() else {
+        return Ok(None);
+    };
+    let active_profile = secrets.active_profile.clone();
+    let Some(credentials) = secrets.profiles.get_mut(&active_profile) else {
+        return Ok(None);
+    };
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let client_id = credentials.client_id.clone();
+    let created_at = match credentials.credentials_created_at {
+        Some(created_at) => created_at,
+        None => {
+            credentials.credentials_created_at = Some(now);
+            write_secrets_file(&secrets)?;
+            now
+        }
+    };
+
+    let age_days = now.saturating_sub(created_at) / 86_400;
+    let warn_after_days = load_settings().credentials_rotation_warning_days;
+    let rotation_recommended = warn_after_days > 0 && age_days >= warn_after_days;
+    if rotation_recommended {
+        println!("⚠️  These credentials are {} days old; consider rotating them", age_days);
+    }
+
+    Ok(Some(CredentialsAge {
+        client_id,
+        created_at,
+        age_days,
+        warn_after_days,
+        rotation_recommended,
+    }))
+}
+
+fn require_credentials() -> Result<SophosCredentials, String> {
+    load_credentials().ok_or_else(|| "No Sophos credentials saved. Save credentials first.".to_string())
+}
+
 fn is_cache_valid(timestamp: u64) -> bool {
     let now = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
-    let cache_age_hours = (now - timestamp) / 3600;
-    cache_age_hours < CACHE_DURATION_HOURS
+    is_cache_valid_at(timestamp, now)
 }
 
-fn load_cached_data(tenant_id: &str) -> Option<Vec<SophosEndpoint>> {
-    let cache_path = get_cache_path();
-    
-    if !cache_path.exists() {
+fn is_cache_valid_at(timestamp: u64, now: u64) -> bool {
+    // A timestamp from the future means one of the clocks was wrong; the age is unknown
+    let Some(cache_age_secs) = now.checked_sub(timestamp) else {
+        println!("⚠️  Cache timestamp is {}s in the future (clock skew?), refetching", timestamp - now);
+        return false;
+    };
+    cache_age_secs / 3600 < load_settings().cache_duration_hours
+}
+
+fn load_cached_data(cache_path: &std::path::Path, tenant_id: &str) -> Option<Vec<SophosEndpoint>> {
+    if !load_settings().cache_enabled {
+        println!("🚫 Caching disabled, fetching fresh data");
+        return None;
+    }
+
+
+    if !cache_file::exists(cache_path) {
         println!("📂 No cache file found");
         return None;
     }
 
-    match fs::read_to_string(&cache_path) {
+    match cache_file::read_text(cache_path) {
         Ok(content) => {
             match serde_json::from_str::<CachedData>(&content) {
                 Ok(cached_data) => {
+                    if cached_data.schema_version > CACHE_SCHEMA_VERSION {
+                        println!("⚠️  Cache was written by a newer version (schema v{}), ignoring cache", cached_data.schema_version);
+                        return None;
+                    }
+
                     if cached_data.tenant_id != tenant_id {
                         println!("🔄 Cache tenant mismatch, ignoring cache");
                         return None;
                     }
+
+                    if cached_data.checksum_matches() == Some(false) {
+                        println!("⚠️  Cache checksum mismatch (corrupted or edited on disk), refetching");
+                        return None;
+                    }
                     
                     if is_cache_valid(cached_data.timestamp) {
                         println!("✅ Using cached data ({} endpoints, {} hours old)", 
@@ -105,218 +1013,1336 @@ fn load_cached_data(tenant_id: &str) -> Option<Vec<SophosEndpoint>> {
     }
 }
 
-fn save_cached_data(endpoints: &[SophosEndpoint], tenant_id: &str) {
-    let cache_path = get_cache_path();
+fn read_cache_file(cache_path: &std::path::Path) -> Result<CachedData, String> {
+    if !load_settings().cache_enabled {
+        return Err("Caching is disabled in settings".to_string());
+    }
+
+    if !cache_file::exists(cache_path) {
+        return Err("No cached endpoints available. Fetch endpoints first.".to_string());
+    }
+
+    let content = cache_file::read_text(cache_path)
+        .map_err(|e| format!("Failed to read cache: {}", e))?;
+    serde_json::from_str(&content).map_err(|e| format!("Failed to parse cache: {}", e))
+}
+
+/// Reads whatever inventory is cached, regardless of age or tenant.
+/// Used by the local-only commands that should never hit the network.
+fn load_cached_endpoints() -> Result<Vec<SophosEndpoint>, String> {
+    Ok(read_cache_file(&get_cache_path())?.endpoints)
+}
+
+/// How a fetch result is written to the cache.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum CacheWriteMode {
+    /// A full, unfiltered fetch: the result is the whole inventory
+    Replace,
+    /// A filtered or partial result: merged by id so excluded endpoints aren't lost
+    Merge,
+}
+
+fn save_cached_data(
+    cache_path: &std::path::Path,
+    endpoints: &[SophosEndpoint],
+    tenant_id: &str,
+    mode: CacheWriteMode,
+) {
+    if !load_settings().cache_enabled {
+        println!("🚫 Caching disabled, not persisting {} endpoints", endpoints.len());
+        return;
+    }
+
+    match write_cache(cache_path, endpoints, tenant_id, mode) {
+        Ok(cached_data) => println!("💾 Data cached successfully ({} endpoints)", cached_data.endpoints.len()),
+        Err(e) => println!("❌ {}", e),
+    }
+}
+
+/// The one write path for endpoint caches: merges per `mode`, snapshots the previous
+/// cache, writes with a fresh checksum, then refreshes the summary and raises refresh
+/// alerts. Returns what was written. Doesn't check `cache_enabled`; callers decide.
+fn write_cache(
+    cache_path: &std::path::Path,
+    endpoints: &[SophosEndpoint],
+    tenant_id: &str,
+    mode: CacheWriteMode,
+) -> Result<CachedData, String> {
+    let endpoints = match mode {
+        CacheWriteMode::Replace => endpoints.to_vec(),
+        CacheWriteMode::Merge => match read_cache_file(cache_path) {
+            Ok(existing) if existing.tenant_id == tenant_id => {
+                merge_endpoints(existing.endpoints, endpoints.to_vec())
+            }
+            _ => endpoints.to_vec(),
+        },
+    };
+
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap()
         .as_secs();
 
-    let cached_data = CachedData {
-        endpoints: endpoints.to_vec(),
-        timestamp,
-        tenant_id: tenant_id.to_string(),
+    snapshots::snapshot_previous(cache_path);
+
+    let cached_data = CachedData::new(endpoints, timestamp, tenant_id.to_string());
+    let json_content = serde_json::to_string_pretty(&cached_data)
+        .map_err(|e| format!("Failed to serialize cache: {}", e))?;
+    cache_file::write_text(cache_path, &json_content).map_err(|e| format!("Failed to save cache: {}", e))?;
+
+    refresh_summary(cache_path, &cached_data);
+    alerts::emit_refresh_alerts(cache_path);
+    Ok(cached_data)
+}
+
+/// Merges endpoints into the main cache by id, updating existing records and adding new
+/// ones, and returns how many were added or updated. Unlike a full-replace save, endpoints
+/// missing from `new` stay cached, so a filtered or partial result can't prune the
+/// inventory; only a full fetch removes endpoints. Refuses to merge into another tenant's
+/// cache.
+#[tauri::command]
+async fn merge_endpoints_into_cache(new: Vec<SophosEndpoint>, tenant_id: String) -> Result<usize, String> {
+    if !load_settings().cache_enabled {
+        return Err("Caching is disabled in settings".to_string());
+    }
+
+    let cache_path = get_cache_path();
+    let existing = if cache_file::exists(&cache_path) {
+        let cached_data = read_cache_file(&cache_path)?;
+        if cached_data.tenant_id != tenant_id {
+            return Err(format!(
+                "Cache belongs to tenant {}, not {}; fetch or clear the cache first",
+                cached_data.tenant_id, tenant_id
+            ));
+        }
+        cached_data.endpoints
+    } else {
+        Vec::new()
+    };
+
+    let (added, updated) = {
+        let existing_ids: HashSet<&str> = existing.iter().map(|endpoint| endpoint.id.as_str()).collect();
+        let new_ids: HashSet<&str> = new.iter().map(|endpoint| endpoint.id.as_str()).collect();
+        let added = new_ids.difference(&existing_ids).count();
+        (added, new_ids.len() - added)
     };
 
-    match serde_json::to_string_pretty(&cached_data) {
-        Ok(json_content) => {
-            match fs::write(&cache_path, json_content) {
-                Ok(_) => println!("💾 Data cached successfully ({} endpoints)", endpoints.len()),
-                Err(e) => println!("❌ Failed to save cache: {}", e),
+    let cached_data = write_cache(&cache_path, &new, &tenant_id, CacheWriteMode::Merge)?;
+
+    println!(
+        "🔀 Merged into cache: {} added, {} updated ({} endpoints total)",
+        added,
+        updated,
+        cached_data.endpoints.len()
+    );
+    Ok(added + updated)
+}
+
+/// Recomputes the tenant summary after its full inventory cache was rewritten. Filtered
+/// caches (health subsets, slim `fields` fetches) don't describe the whole tenant.
+fn refresh_summary(cache_path: &std::path::Path, cached_data: &CachedData) {
+    if cache_path == get_cache_path() || cache_path == get_tenant_cache_path(&cached_data.tenant_id) {
+        summary::write_summary(&cached_data.tenant_id, &cached_data.endpoints);
+    }
+}
+
+/// Edits the cached endpoint list in place. Keeps the original timestamp so a local edit
+/// (e.g. dropping deleted machines) doesn't make the rest of the data look fresh.
+fn edit_cached_endpoints(edit: impl FnOnce(&mut Vec<SophosEndpoint>)) -> Result<(), String> {
+    let cache_path = get_cache_path();
+    let mut cached_data = read_cache_file(&cache_path)?;
+    edit(&mut cached_data.endpoints);
+    cached_data.checksum = Some(endpoints_checksum(&cached_data.endpoints));
+
+    let json_content = serde_json::to_string_pretty(&cached_data)
+        .map_err(|e| format!("Failed to serialize cache: {}", e))?;
+    cache_file::write_text(&cache_path, &json_content).map_err(|e| format!("Failed to save cache: {}", e))?;
+    refresh_summary(&cache_path, &cached_data);
+    Ok(())
+}
+
+/// Keeps one endpoint per `key`, the one with the latest parsed `lastSeen`; a record with
+/// no timestamp loses to one with. Endpoints without a key can't be matched and are kept
+/// as-is. Order follows each key's first appearance.
+fn keep_latest_by(
+    endpoints: Vec<SophosEndpoint>,
+    key: impl Fn(&SophosEndpoint) -> Option<String>,
+) -> Vec<SophosEndpoint> {
+    let mut deduped: Vec<SophosEndpoint> = Vec::with_capacity(endpoints.len());
+    let mut index_by_key: HashMap<String, usize> = HashMap::new();
+
+    for endpoint in endpoints {
+        let Some(key) = key(&endpoint) else {
+            deduped.push(endpoint);
+            continue;
+        };
+
+        match index_by_key.get(&key) {
+            Some(&index) => {
+                if endpoint.last_seen_at() > deduped[index].last_seen_at() {
+                    deduped[index] = endpoint;
+                }
+            }
+            None => {
+                index_by_key.insert(key, deduped.len());
+                deduped.push(endpoint);
             }
         }
-        Err(e) => println!("❌ Failed to serialize cache: {}", e),
     }
+
+    deduped
+}
+
+/// Collapses endpoints sharing a hostname (see `hostname_key`), keeping the most recently
+/// seen record. Endpoints without a hostname can't be matched and are kept as-is.
+fn dedup_by_hostname(endpoints: Vec<SophosEndpoint>) -> Vec<SophosEndpoint> {
+    keep_latest_by(endpoints, SophosEndpoint::hostname_key)
+}
+
+fn apply_dedup_strategy(endpoints: Vec<SophosEndpoint>, strategy: DedupStrategy) -> Vec<SophosEndpoint> {
+    match strategy {
+        DedupStrategy::ById => endpoints,
+        DedupStrategy::ByHostname | DedupStrategy::ByIdThenHostname => {
+            let before = endpoints.len();
+            let deduped = dedup_by_hostname(endpoints);
+            if deduped.len() != before {
+                println!("   🔁 Collapsed {} re-enrolled endpoints by hostname", before - deduped.len());
+            }
+            deduped
+        }
+    }
+}
+
+/// Returns the inventory for the saved credentials, from cache when it's still fresh.
+async fn fetch_with_saved_credentials(access_token: String) -> Result<Vec<SophosEndpoint>, String> {
+    let credentials = require_credentials()?;
+
+    fetch_sophos_endpoints(
+        access_token,
+        credentials.tenant_id,
+        credentials.region,
+        Some(credentials.allow_unknown_region),
+        None,
+        None,
+        None,
+    )
+    .await
+}
+
+/// Fetches the inventory with the saved credentials and reports how many retries, pages
+/// and 429s it took. Always goes to Sophos, since the counters describe the API rather
+/// than the cache; the result still refreshes the cache.
+#[tauri::command]
+async fn fetch_sophos_endpoints_with_stats(access_token: String) -> Result<FetchResult, String> {
+    let credentials = require_credentials()?;
+    let dedup_strategy = load_settings().dedup_strategy;
+    let cache_path = get_cache_path();
+    let started = std::time::Instant::now();
+
+    let client = http_client()?;
+    let base_url = api_host::resolve_endpoints_base_url(&client, &access_token, &credentials).await?;
+    let mut result = fetch_endpoint_pages_with(
+        &client,
+        &base_url,
+        &access_token,
+        &credentials.tenant_id,
+        &[],
+        dedup_strategy,
+        |_, _| {},
+    )
+    .await?;
+
+    save_cached_data(&cache_path, &result.endpoints, &credentials.tenant_id, CacheWriteMode::Replace);
+    result.endpoints = apply_dedup_strategy(result.endpoints, dedup_strategy);
+    audit::record_fetch(&credentials.tenant_id, result.endpoints.len(), false, started.elapsed());
+    Ok(result)
 }
 
 #[tauri::command]
 async fn clear_cache() -> Result<String, String> {
     let cache_path = get_cache_path();
-    
-    if cache_path.exists() {
-        match fs::remove_file(&cache_path) {
-            Ok(_) => {
-                println!("🗑️  Cache cleared successfully");
-                Ok("Cache cleared successfully".to_string())
+    let mut removed = 0;
+
+    for path in [cache_path.clone(), snapshots::previous_snapshot_path(&cache_path)] {
+        if cache_file::remove(&path).map_err(|e| format!("Failed to clear cache: {}", e))? {
+            removed += 1;
+        }
+    }
+
+    // Caches for tenants fetched via override
+    if let Ok(entries) = fs::read_dir(get_app_data_dir()) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with(&format!("{}_", TENANT_CACHE_PREFIX))
+                && (name.ends_with(".json") || name.ends_with(".json.gz"))
+            {
+                fs::remove_file(entry.path()).map_err(|e| format!("Failed to clear cache {}: {}", name, e))?;
+                removed += 1;
             }
-            Err(e) => Err(format!("Failed to clear cache: {}", e))
         }
+    }
+
+    removed += summary::remove_all_summaries()?;
+
+    if removed > 0 {
+        println!("🗑️  Cache cleared successfully");
+        Ok("Cache cleared successfully".to_string())
     } else {
         Ok("No cache file to clear".to_string())
     }
 }
 
+/// True for files this app writes into its data dir; anything else there is left alone,
+/// including `PINNED_CHAIN_FILE`, which the admin places there for `pin_sophos_cert`.
+fn is_app_managed_file(name: &str) -> bool {
+    [
+        SECRETS_FILE,
+        settings::SETTINGS_FILE,
+        token::TOKEN_FILE,
+        api_host::API_HOST_FILE,
+        ui_state::UI_STATE_FILE,
+        views::VIEWS_FILE,
+        audit::AUDIT_FILE,
+    ]
+    .contains(&name)
+        || (name.starts_with(TENANT_CACHE_PREFIX) && (name.ends_with(".json") || name.ends_with(".json.gz")))
+        || (name.starts_with(summary::SUMMARY_PREFIX) && name.ends_with(".json"))
+}
+
+/// Deletes every file the app manages (credentials, caches, snapshots, token, settings)
+/// from its data dir. Does nothing unless `confirm` is set. A pinned CA chain is kept, so
+/// certificate pinning still works afterwards.
+#[tauri::command]
+async fn reset_all_data(token_state: tauri::State<'_, TokenState>, confirm: bool) -> Result<String, String> {
+    if !confirm {
+        return Err("Resetting deletes saved credentials, caches and settings. Pass confirm to proceed.".to_string());
+    }
+
+    let data_dir = get_app_data_dir();
+    let entries = fs::read_dir(&data_dir).map_err(|e| format!("Failed to read {}: {}", data_dir.display(), e))?;
+
+    let mut removed = Vec::new();
+    let mut failed = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !entry.path().is_file() || !is_app_managed_file(&name) {
+            continue;
+        }
+        match fs::remove_file(entry.path()) {
+            Ok(_) => removed.push(name),
+            Err(e) => failed.push(format!("{} ({})", name, e)),
+        }
+    }
+    removed.sort();
+
+    token_state.clear().await;
+
+    println!("🗑️  Reset removed {} files from {}", removed.len(), data_dir.display());
+    if !failed.is_empty() {
+        return Err(format!(
+            "Removed {} but could not delete: {}",
+            if removed.is_empty() { "nothing".to_string() } else { removed.join(", ") },
+            failed.join(", ")
+        ));
+    }
+
+    if removed.is_empty() {
+        Ok("No app data to remove".to_string())
+    } else {
+        Ok(format!("Removed {} files: {}", removed.len(), removed.join(", ")))
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CacheFileInfo {
+    name: String,
+    size_bytes: u64,
+    modified: Option<String>,
+    tenant_id: Option<String>,
+}
+
+/// Just enough of a cache file to say which tenant it belongs to.
+#[derive(Deserialize)]
+struct CacheTenantProbe {
+    tenant_id: Option<String>,
+}
+
+/// Lists the files the app keeps in its data dir with size, modification time and, for
+/// endpoint caches, the tenant they hold. Files that can't be read are still listed.
+#[tauri::command]
+async fn list_cache_files() -> Result<Vec<CacheFileInfo>, String> {
+    let data_dir = get_app_data_dir();
+    let entries = fs::read_dir(&data_dir).map_err(|e| format!("Failed to read {}: {}", data_dir.display(), e))?;
+
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !is_app_managed_file(&name) {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        if !metadata.is_file() {
+            continue;
+        }
+
+        let modified = metadata
+            .modified()
+            .ok()
+            .map(|modified| chrono::DateTime::<chrono::Utc>::from(modified).to_rfc3339());
+
+        let tenant_id = if name.starts_with(TENANT_CACHE_PREFIX) {
+            fs::read(entry.path())
+                .ok()
+                .and_then(|bytes| cache_file::decode(bytes).ok())
+                .and_then(|content| serde_json::from_str::<CacheTenantProbe>(&content).ok())
+                .and_then(|probe| probe.tenant_id)
+        } else {
+            None
+        };
+
+        files.push(CacheFileInfo {
+            name,
+            size_bytes: metadata.len(),
+            modified,
+            tenant_id,
+        });
+    }
+
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(files)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct StorageStatus {
+    path: String,
+    is_fallback: bool,
+    warning: Option<String>,
+}
+
+#[tauri::command]
+async fn get_storage_status() -> Result<StorageStatus, String> {
+    let path = get_app_data_dir();
+    let is_fallback = app_data_dir_is_fallback();
+    let warning = is_fallback.then(|| {
+        format!(
+            "No user data directory was found. Cache and settings are stored in the temporary directory {} and credentials cannot be saved.",
+            path.display()
+        )
+    });
+
+    Ok(StorageStatus {
+        path: path.display().to_string(),
+        is_fallback,
+        warning,
+    })
+}
+
+/// Turns an IO error from the storage preflight into a message the user can act on.
+fn describe_storage_error(action: &str, path: &std::path::Path, e: &std::io::Error) -> String {
+    let reason = match e.kind() {
+        std::io::ErrorKind::PermissionDenied => "permission denied",
+        std::io::ErrorKind::ReadOnlyFilesystem => "the file system is read-only",
+        std::io::ErrorKind::StorageFull => "the disk is full",
+        std::io::ErrorKind::NotFound | std::io::ErrorKind::NotADirectory | std::io::ErrorKind::InvalidInput => {
+            "the path is invalid"
+        }
+        _ => "unexpected error",
+    };
+    format!("Cannot {} {}: {} ({})", action, path.display(), reason, e)
+}
+
+/// Creates the app data dir and writes, reads back and deletes a probe file, so the UI
+/// can warn before credentials or caches fail to save.
+#[tauri::command]
+async fn check_storage_writable() -> Result<(), String> {
+    let dir = get_app_data_dir();
+    fs::create_dir_all(&dir).map_err(|e| describe_storage_error("create data directory", &dir, &e))?;
+
+    let probe = dir.join(format!(".write_check_{}", std::process::id()));
+    fs::write(&probe, b"ok").map_err(|e| describe_storage_error("write to", &dir, &e))?;
+    let read_back = fs::read(&probe);
+    let removed = fs::remove_file(&probe);
+
+    match read_back {
+        Ok(content) if content == b"ok" => {}
+        Ok(_) => return Err(format!("Cannot use {}: a test file did not read back as written", dir.display())),
+        Err(e) => return Err(describe_storage_error("read from", &dir, &e)),
+    }
+    removed.map_err(|e| describe_storage_error("delete files in", &dir, &e))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+async fn get_offline_endpoints() -> Result<Vec<SophosEndpoint>, String> {
+    // Endpoints with no reported status are treated as offline/unknown
+    let mut endpoints: Vec<SophosEndpoint> = load_cached_endpoints()?
+        .into_iter()
+        .filter(|endpoint| endpoint.online != Some(true))
+        .collect();
+
+    // Most recently seen first; endpoints never seen go last
+    endpoints.sort_by(|a, b| b.last_seen.cmp(&a.last_seen));
+
+    Ok(endpoints)
+}
+
+#[tauri::command]
+async fn get_online_endpoints() -> Result<Vec<SophosEndpoint>, String> {
+    let endpoints = load_cached_endpoints()?
+        .into_iter()
+        .filter(|endpoint| endpoint.online == Some(true))
+        .collect();
+
+    Ok(endpoints)
+}
+
+/// Endpoints with no usable IP: all fields empty, or only invalid/loopback entries.
+#[tauri::command]
+async fn find_endpoints_without_ip() -> Result<Vec<SophosEndpoint>, String> {
+    let endpoints = load_cached_endpoints()?
+        .into_iter()
+        .filter(|endpoint| endpoint.usable_ip_addresses().is_empty())
+        .collect();
+
+    Ok(endpoints)
+}
+
+#[tauri::command]
+async fn save_sophos_credentials(credentials: SophosCredentials) -> Result<SophosCredentials, String> {
+    let mut credentials = credentials.normalized()?;
+    validate_region(&credentials.region, credentials.allow_unknown_region)?;
+
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let mut secrets = load_secrets_file().unwrap_or_default();
+    // Keep the first-saved date while the client id stays the same; a new id starts over
+    credentials.credentials_created_at = secrets
+        .profiles
+        .get(&secrets.active_profile)
+        .filter(|previous| previous.client_id == credentials.client_id)
+        .and_then(|previous| previous.credentials_created_at)
+        .or(Some(now));
+    secrets.profiles.insert(secrets.active_profile.clone(), credentials.clone());
+    write_secrets_file(&secrets)?;
+
+    println!("🔐 Credentials saved (region: {})", credentials.region);
+    if credentials_from_env().is_some() {
+        println!("⚠️  Credential environment variables are set and take precedence over the saved credentials");
+    }
+    Ok(credentials)
+}
+
+/// Parses credentials JSON from another source and normalizes and validates it the same
+/// way `save_sophos_credentials` does, without saving anything. Errors name the field at
+/// fault.
+#[tauri::command]
+async fn parse_credentials_json(content: String) -> Result<SophosCredentials, String> {
+    let value: serde_json::Value =
+        serde_json::from_str(&content).map_err(|e| format!("Credentials are not valid JSON: {}", e))?;
+    let Some(fields) = value.as_object() else {
+        return Err("Credentials must be a JSON object".to_string());
+    };
+    if fields.contains_key("profiles") {
+        return Err("This is a secrets file with profiles; paste a single set of credentials".to_string());
+    }
+
+    for field in ["client_id", "client_secret", "tenant_id", "region"] {
+        match fields.get(field) {
+            None | Some(serde_json::Value::Null) => return Err(format!("Credential field '{}' is missing", field)),
+            Some(serde_json::Value::String(_)) => {}
+            Some(_) => return Err(format!("Credential field '{}' must be a string", field)),
+        }
+    }
+    if fields.get("allow_unknown_region").is_some_and(|value| !value.is_boolean()) {
+        return Err("Credential field 'allow_unknown_region' must be true or false".to_string());
+    }
+
+    let mut credentials: SophosCredentials =
+        serde_json::from_value(value).map_err(|e| format!("Invalid credentials: {}", e))?;
+    // Tracked by the app once saved; an imported value means nothing here
+    credentials.credentials_created_at = None;
+
+    let credentials = credentials.normalized()?;
+    validate_region(&credentials.region, credentials.allow_unknown_region)?;
+    Ok(credentials)
+}
+
+/// Moves the saved credentials to another data region, e.g. after Sophos migrated the
+/// tenant. The cached token and endpoint caches belong to the old region and are cleared.
+#[tauri::command]
+async fn update_region(token_state: tauri::State<'_, TokenState>, region: String) -> Result<SophosCredentials, String> {
+    let mut secrets = load_secrets_file().ok_or("No Sophos credentials saved. Save credentials first.")?;
+    let active_profile = secrets.active_profile.clone();
+    let credentials = secrets
+        .profiles
+        .get_mut(&active_profile)
+        .ok_or("No Sophos credentials saved. Save credentials first.")?;
+
+    let region = region.trim().to_lowercase();
+    validate_region(&region, credentials.allow_unknown_region)?;
+    credentials.region = region;
+    let updated = credentials.clone();
+    write_secrets_file(&secrets)?;
+
+    token::invalidate_token(token_state).await?;
+    api_host::forget_api_host()?;
+    clear_cache().await?;
+
+    println!("🌍 Region updated to '{}'", updated.region);
+    if credentials_from_env().is_some() {
+        println!("⚠️  Credential environment variables are set and take precedence over the saved credentials");
+    }
+    Ok(updated)
+}
+
+#[tauri::command]
+async fn load_sophos_credentials() -> Result<Option<SophosCredentials>, String> {
+    Ok(load_credentials())
+}
+
 #[tauri::command]
 async fn get_sophos_access_token(
+    token_state: tauri::State<'_, TokenState>,
     client_id: String,
     client_secret: String,
 ) -> Result<String, String> {
-    let client = reqwest::Client::new();
-    
-    let mut params = HashMap::new();
-    params.insert("grant_type", "client_credentials");
-    params.insert("client_id", &client_id);
-    params.insert("client_secret", &client_secret);
-    params.insert("scope", "token");
+    token_state.get_or_refresh(&client_id, &client_secret).await
+}
+
+async fn fetch_whoami(client: &impl SophosClient, access_token: &str) -> Result<WhoAmI, SophosError> {
+    let response = client
+        .get_whoami(access_token)
+        .await
+        .map_err(|e| SophosError::Request(format!("Whoami request failed: {}", e)))?;
+
+    if !(200..300).contains(&response.status) {
+        return Err(SophosError::Api {
+            status: response.status,
+            message: format!("Whoami request failed ({}): {}", response.status, describe_api_error(&response.body)),
+        });
+    }
+
+    serde_json::from_str(&response.body)
+        .map_err(|e| SophosError::Parse(format!("Failed to parse whoami response: {}", e)))
+}
+
+/// Extracts the region code from a data-region host like `https://api-eu01.central.sophos.com`.
+fn region_from_api_host(api_host: &str) -> Option<String> {
+    let host = api_host.trim_start_matches("https://").trim_start_matches("http://");
+    host.strip_prefix("api-")?
+        .split('.')
+        .next()
+        .filter(|region| !region.is_empty())
+        .map(|region| region.to_string())
+}
+
+/// Updates the saved region for `tenant_id` after whoami showed the configured one was wrong.
+fn persist_corrected_region(tenant_id: &str, region: &str) -> Result<(), String> {
+    let mut secrets = load_secrets_file().ok_or("No saved credentials to correct")?;
+    let active_profile = secrets.active_profile.clone();
+    let credentials = secrets
+        .profiles
+        .get_mut(&active_profile)
+        .filter(|credentials| credentials.tenant_id == tenant_id)
+        .ok_or("Saved credentials belong to a different tenant")?;
+
+    credentials.region = region.to_string();
+    write_secrets_file(&secrets)?;
+    println!("🔐 Saved corrected region '{}' to credentials", region);
+    Ok(())
+}
+
+/// Fetches the full detail record for one endpoint (includes fields the list omits,
+/// such as `assignedProducts`).
+async fn fetch_endpoint_detail(
+    client: &impl SophosClient,
+    base_url: &str,
+    access_token: &str,
+    tenant_id: &str,
+    endpoint_id: &str,
+) -> Result<serde_json::Value, SophosError> {
+    let response = client
+        .get_resource(&format!("{}/{}", base_url, endpoint_id), access_token, tenant_id)
+        .await
+        .map_err(|e| SophosError::Request(format!("Request failed for endpoint {}: {}", endpoint_id, e)))?;
+
+    if !(200..300).contains(&response.status) {
+        let detail = describe_api_error(&response.body);
+        return Err(SophosError::Api {
+            status: response.status,
+            message: format!("Endpoint detail request failed for {} ({}): {}", endpoint_id, response.status, detail),
+        });
+    }
+
+    serde_json::from_str(&response.body)
+        .map_err(|e| SophosError::Parse(format!("Failed to parse endpoint {}: {}", endpoint_id, e)))
+}
 
+/// Asks Sophos how many endpoints match `query` without paging through them, using a
+/// one-item page with `pageTotal=true`.
+async fn fetch_endpoint_total(
+    client: &impl SophosClient,
+    base_url: &str,
+    access_token: &str,
+    tenant_id: &str,
+    query: &[(&str, String)],
+) -> Result<u64, SophosError> {
+    let mut count_query = vec![("pageSize", "1".to_string()), ("pageTotal", "true".to_string())];
+    count_query.extend(query.iter().cloned());
     let response = client
-        .post("https://id.sophos.com/api/v2/oauth2/token")
-        .form(&params)
-        .send()
+        .get_endpoints_page(base_url, access_token, tenant_id, &count_query)
         .await
-        .map_err(|e| format!("Request failed: {}", e))?;
+        .map_err(|e| SophosError::Request(format!("Count request failed: {}", e)))?;
 
-    if !response.status().is_success() {
-        return Err(format!("Authentication failed: {}", response.status()));
+    if !(200..300).contains(&response.status) {
+        return Err(SophosError::Api {
+            status: response.status,
+            message: format!("Count request failed ({}): {}", response.status, describe_api_error(&response.body)),
+        });
     }
 
-    let token_response: SophosTokenResponse = response
-        .json()
-        .await
-        .map_err(|e| format!("Failed to parse response: {}", e))?;
+    let page: SophosEndpointsResponse = serde_json::from_str(&response.body)
+        .map_err(|e| SophosError::Parse(format!("Failed to parse count response: {}", e)))?;
 
-    Ok(token_response.access_token)
+    page.pages
+        .as_ref()
+        .and_then(|pages| pages.get("items"))
+        .and_then(|items| items.as_u64())
+        .ok_or_else(|| SophosError::Parse("Count response did not include a total".to_string()))
 }
 
-#[tauri::command]
-async fn fetch_sophos_endpoints(
-    access_token: String,
-    tenant_id: String,
-    region: String,
-) -> Result<Vec<SophosEndpoint>, String> {
-    // Check cache first
-    if let Some(cached_endpoints) = load_cached_data(&tenant_id) {
-        return Ok(cached_endpoints);
+/// The app's shared HTTP clients. Tests run without an app, so they get one per thread.
+fn client_state() -> Result<&'static ClientState, String> {
+    #[cfg(test)]
+    if app_handle().is_none() {
+        return Ok(test_support::client_state());
     }
 
-    let client = reqwest::Client::new();
-    let base_url = format!("https://api-{}.central.sophos.com/endpoint/v1/endpoints", region);
-    
+    let app = app_handle().ok_or("App is not initialized yet")?;
+    Ok(app.state::<ClientState>().inner())
+}
+
+/// The app's shared token state. Tests run without an app, so they get one per thread.
+fn token_state() -> Result<&'static TokenState, String> {
+    #[cfg(test)]
+    if app_handle().is_none() {
+        return Ok(test_support::token_state());
+    }
+
+    let app = app_handle().ok_or("App is not initialized yet")?;
+    Ok(app.state::<TokenState>().inner())
+}
+
+/// Swaps a token rejected mid-fetch for a fresh one from the shared token state, using
+/// the saved credentials.
+async fn reacquire_access_token(client: &impl SophosClient, rejected_token: &str) -> Result<String, String> {
+    let credentials = require_credentials()?;
+
+    token_state()?
+        .refresh_rejected(client, rejected_token, &credentials.client_id, &credentials.client_secret)
+        .await
+}
+
+/// Pages always logged individually; past this, only every `PAGE_LOG_INTERVAL`th page is
+/// unless `verbose_pagination_log` is set. Warnings are never throttled.
+const PAGE_LOG_THRESHOLD: u32 = 10;
+const PAGE_LOG_INTERVAL: u32 = 10;
+
+fn should_log_page(page: u32, verbose: bool) -> bool {
+    verbose || page <= PAGE_LOG_THRESHOLD || page % PAGE_LOG_INTERVAL == 0
+}
+
+/// First backoff after a failed page; doubles on each further retry of the same page.
+const PAGE_RETRY_BASE_DELAY_MS: u64 = 1000;
+const PAGE_RETRY_MAX_DELAY_MS: u64 = 30_000;
+
+/// A paginated fetch along with how much trouble it took, for automation that alerts
+/// when the API starts struggling.
+#[derive(Debug, Serialize, Deserialize)]
+struct FetchResult {
+    endpoints: Vec<SophosEndpoint>,
+    /// Page requests repeated after a transient failure or a rejected token
+    retries: u32,
+    pages: u32,
+    /// Responses that were 429 Too Many Requests
+    rate_limited_count: u32,
+}
+
+/// Walks every page of the endpoint list, deduplicating by id as it goes.
+/// `query` carries extra filter parameters sent with every page request.
+async fn fetch_endpoint_pages(
+    client: &impl SophosClient,
+    base_url: &str,
+    access_token: &str,
+    tenant_id: &str,
+    query: &[(&str, String)],
+    dedup_strategy: DedupStrategy,
+) -> Result<Vec<SophosEndpoint>, SophosError> {
+    fetch_endpoint_pages_with(client, base_url, access_token, tenant_id, query, dedup_strategy, |_, _| {})
+        .await
+        .map(|result| result.endpoints)
+}
+
+/// `fetch_endpoint_pages`, calling `on_page` with each page number and the endpoints it
+/// added as soon as the page is in, and returning retry counters with the endpoints.
+///
+/// A 429, a 5xx or a transport failure retries the same page up to `max_page_retries`
+/// times with exponential backoff.
+async fn fetch_endpoint_pages_with(
+    client: &impl SophosClient,
+    base_url: &str,
+    access_token: &str,
+    tenant_id: &str,
+    query: &[(&str, String)],
+    dedup_strategy: DedupStrategy,
+    mut on_page: impl FnMut(u32, &[SophosEndpoint]) + Send,
+) -> Result<FetchResult, SophosError> {
     let mut all_endpoints = Vec::new();
     let mut seen_ids = std::collections::HashSet::new();
     let mut page_token: Option<String> = None;
     let mut page_count = 0;
-    let page_size = 100; // Maximum page size for better performance
+    let page_size = load_settings().page_size;
+    let pagination_mode = load_settings().pagination_mode;
+    let mut total_pages: Option<u64> = None;
+    let mut access_token = access_token.to_string();
+    let mut reauthenticated = false;
+    let mut last_pages: Option<serde_json::Value> = None;
+    let mut reported_total: Option<u64> = None;
+    let mut skipped_total = 0;
+    let max_page_retries = load_settings().max_page_retries;
+    let verbose_log = load_settings().verbose_pagination_log;
+    let mut page_retries = 0;
+    let mut retries = 0;
+    let mut rate_limited_count = 0;
+
+    let mut timer = FetchTimer::start();
 
     println!("📡 Fetching endpoint inventory with pagination...");
     println!("   Page size: {}", page_size);
 
     loop {
         page_count += 1;
+        let page_started = std::time::Instant::now();
         
-        // Build URL with pagination parameters
-        let mut url = format!("{}?pageSize={}", base_url, page_size);
-        if let Some(ref token) = page_token {
-            url.push_str(&format!("&pageFromKey={}", token));
+        // Build request with pagination parameters
+        let mut page_query: Vec<(&str, String)> = vec![("pageSize", page_size.to_string())];
+        page_query.extend_from_slice(query);
+        match pagination_mode {
+            PaginationMode::Key => {
+                if let Some(ref token) = page_token {
+                    page_query.push(("pageFromKey", token.clone()));
+                }
+            }
+            PaginationMode::Offset => page_query.push(("page", page_count.to_string())),
+        }
+        if page_count == 1 {
+            // Sophos only computes totals on request, and only for the first page
+            page_query.push(("pageTotal", "true".to_string()));
         }
 
-        let response = client
-            .get(&url)
-            .header("Authorization", format!("Bearer {}", &access_token))
-            .header("X-Tenant-ID", &tenant_id)
-            .header("Accept", "application/json")
-            .send()
-            .await
-            .map_err(|e| format!("Request failed on page {}: {}", page_count, e))?;
+        let response = client.get_endpoints_page(base_url, &access_token, tenant_id, &page_query).await;
 
-        if !response.status().is_success() {
-            let status = response.status();
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(format!("API request failed on page {} ({}): {}", page_count, status, error_text));
+        // Transient failures: back off and request the same page again
+        let transient = match &response {
+            Ok(response) => response.status == 429 || response.status >= 500,
+            Err(_) => true,
+        };
+        if transient && page_retries < max_page_retries {
+            page_retries += 1;
+            retries += 1;
+            let reason = match &response {
+                Ok(response) if response.status == 429 => {
+                    rate_limited_count += 1;
+                    "rate limited".to_string()
+                }
+                Ok(response) => format!("status {}", response.status),
+                Err(e) => e.clone(),
+            };
+            let delay_ms = (PAGE_RETRY_BASE_DELAY_MS << (page_retries - 1).min(16)).min(PAGE_RETRY_MAX_DELAY_MS);
+            println!(
+                "   🔁 Page {} failed ({}), retry {} of {} in {}ms",
+                page_count, reason, page_retries, max_page_retries, delay_ms
+            );
+            tokio::time::sleep(tokio::time::Duration::from_millis(delay_ms)).await;
+            page_count -= 1;
+            continue;
         }
+        page_retries = 0;
 
-        let response_text = response.text().await.map_err(|e| format!("Failed to read response: {}", e))?;
-        
-        // Debug: Log a sample of the first page response
-        if page_count == 1 {
-            if response_text.len() > 1000 {
-                println!("Sample Sophos API response (page 1): {}", &response_text[..1000]);
-            } else {
-                println!("Full Sophos API response (page 1): {}", response_text);
+        let response =
+            response.map_err(|e| SophosError::Request(format!("Request failed on page {}: {}", page_count, e)))?;
+
+        if !(200..300).contains(&response.status) {
+            let status = response.status;
+            let error_text = response.body;
+            let detail = describe_api_error(&error_text);
+            let status_text = reqwest::StatusCode::from_u16(status)
+                .map_or_else(|_| status.to_string(), |code| code.to_string());
+            let message = format!("API request failed on page {} ({}): {}", page_count, status_text, detail);
+
+            // A token expiring partway through a long fetch shows up as a 401 on a later
+            // page. Retry that page once with a fresh token; a second 401 is a real failure.
+            if status == 401 && page_count > 1 && !reauthenticated {
+                reauthenticated = true;
+                match reacquire_access_token(client, &access_token).await {
+                    Ok(fresh_token) => {
+                        println!("   🔑 Token rejected on page {}, retrying with a fresh token", page_count);
+                        access_token = fresh_token;
+                        retries += 1;
+                        page_count -= 1;
+                        continue;
+                    }
+                    Err(e) => println!("   ⚠️  Could not refresh token: {}", e),
+                }
+            }
+
+            // Failing on the very first page with a region/tenant complaint, after the token
+            // exchange succeeded, is the classic wrong-data-region setup
+            let region_related = page_count == 1
+                && matches!(status, 401 | 403 | 404)
+                && SophosApiError::parse(&error_text).is_some_and(|api_error| api_error.is_region_related());
+            if region_related {
+                return Err(SophosError::RegionMismatch { message });
             }
+
+            return Err(SophosError::Api { status, message });
         }
+
+        let response_text = response.body;
+        let request_elapsed = page_started.elapsed();
         
-        let endpoints_response: SophosEndpointsResponse = serde_json::from_str(&response_text)
-            .map_err(|e| format!("Failed to parse response on page {}: {}", page_count, e))?;
+        let parse_started = std::time::Instant::now();
+        let mut endpoints_response: SophosEndpointsResponse = serde_json::from_str(&response_text)
+            .map_err(|e| SophosError::Parse(format!("Failed to parse response on page {}: {}", page_count, e)))?;
 
-        let page_endpoints = endpoints_response.items.unwrap_or_default();
+        let (page_endpoints, skipped) = endpoints_response.take_endpoints();
+        timer.record_page(page_count, request_elapsed, parse_started.elapsed(), page_endpoints.len(), skipped);
+        skipped_total += skipped;
+        if let Some(pages) = &endpoints_response.pages {
+            if reported_total.is_none() {
+                reported_total = pages.get("items").and_then(|items| items.as_u64());
+            }
+            if total_pages.is_none() {
+                total_pages = pages.get("total").and_then(|total| total.as_u64());
+            }
+            last_pages = Some(pages.clone());
+        }
+        if skipped > 0 {
+            println!("   ⚠️  Skipped {} malformed endpoints on page {}", skipped, page_count);
+        }
         
-        if page_endpoints.is_empty() {
+        if page_endpoints.is_empty() && skipped == 0 {
             println!("   ⚠️  Page {} returned no endpoints, stopping pagination", page_count);
             break;
         }
 
         let page_endpoint_count = page_endpoints.len();
         let mut unique_count = 0;
+        let page_start = all_endpoints.len();
         
-        // Add only unique endpoints (deduplicate by ID)
+        // Add only unique endpoints (deduplicate by ID, unless deduping purely by hostname)
         for endpoint in page_endpoints {
-            if seen_ids.insert(endpoint.id.clone()) {
+            if dedup_strategy == DedupStrategy::ByHostname || seen_ids.insert(endpoint.id.clone()) {
                 all_endpoints.push(endpoint);
                 unique_count += 1;
             }
         }
+        on_page(page_count, &all_endpoints[page_start..]);
         
         if unique_count != page_endpoint_count {
             println!("   ⚠️  Found {} duplicate endpoints on page {}", 
                     page_endpoint_count - unique_count, page_count);
         }
         
-        println!("   ✅ Page {}: Retrieved {} unique endpoints of {} total (Running total: {})", 
-                page_count, unique_count, page_endpoint_count, all_endpoints.len());
+        if should_log_page(page_count, verbose_log) {
+            println!("   ✅ Page {}: Retrieved {} unique endpoints of {} total (Running total: {})", 
+                    page_count, unique_count, page_endpoint_count, all_endpoints.len());
+        } else if page_count == PAGE_LOG_THRESHOLD + 1 {
+            println!("   … Large fetch: logging every {}th page from here", PAGE_LOG_INTERVAL);
+        }
 
-        // Check if there are more pages by looking for nextKey in pages object
-        let has_more = if let Some(pages) = &endpoints_response.pages {
-            pages.get("nextKey").is_some()
-        } else {
-            false
-        };
+        match pagination_mode {
+            PaginationMode::Key => {
+                // Check if there are more pages by looking for nextKey in pages object
+                let has_more = if let Some(pages) = &endpoints_response.pages {
+                    pages.get("nextKey").is_some()
+                } else {
+                    false
+                };
 
-        if has_more {
-            // Extract the next page token
-            if let Some(pages) = &endpoints_response.pages {
-                if let Some(next_key) = pages.get("nextKey") {
-                    if let Some(next_token) = next_key.as_str() {
-                        page_token = Some(next_token.to_string());
+                if has_more {
+                    // Extract the next page token
+                    if let Some(pages) = &endpoints_response.pages {
+                        if let Some(next_key) = pages.get("nextKey") {
+                            if let Some(next_token) = next_key.as_str() {
+                                page_token = Some(next_token.to_string());
+                            } else {
+                                println!("   ⚠️  nextKey found but not a string, stopping pagination");
+                                break;
+                            }
+                        } else {
+                            break;
+                        }
                     } else {
-                        println!("   ⚠️  nextKey found but not a string, stopping pagination");
                         break;
                     }
                 } else {
+                    println!("   ✅ No more pages available");
+                    break;
+                }
+            }
+            PaginationMode::Offset => {
+                // `pages.total` from the first page is the page count; without it, a short
+                // page is the last one
+                let has_more = match total_pages {
+                    Some(total) => u64::from(page_count) < total,
+                    None => page_endpoint_count + skipped >= page_size as usize,
+                };
+                if !has_more {
+                    println!("   ✅ No more pages available");
                     break;
                 }
-            } else {
-                break;
             }
-        } else {
-            println!("   ✅ No more pages available");
-            break;
         }
 
-        // Small delay to avoid rate limiting
-        tokio::time::sleep(tokio::time::Duration::from_millis(100)).await;
+        // Politeness delay between pages. Read each time so a change applies mid-fetch;
+        // values from the file or env are clamped like ones saved through the UI.
+        let page_delay_ms = load_settings().page_delay_ms.min(settings::MAX_PAGE_DELAY_MS);
+        if page_delay_ms > 0 {
+            tokio::time::sleep(tokio::time::Duration::from_millis(page_delay_ms)).await;
+        }
     }
     
     println!("📊 Pagination complete: {} total endpoints retrieved across {} pages", 
              all_endpoints.len(), page_count);
-    
-    // Save to cache for future use
-    save_cached_data(&all_endpoints, &tenant_id);
+    timer.finish();
+    timings::record_pages_metadata(last_pages, reported_total, all_endpoints.len(), skipped_total);
+    if retries > 0 {
+        println!("   🔁 {} page retries ({} rate limited)", retries, rate_limited_count);
+    }
+
+    Ok(FetchResult {
+        endpoints: all_endpoints,
+        retries,
+        pages: page_count,
+        rate_limited_count,
+    })
+}
+
+#[tauri::command]
+async fn fetch_sophos_endpoints(
+    access_token: String,
+    tenant_id: String,
+    region: String,
+    allow_unknown_region: Option<bool>,
+    tenant_override: Option<String>,
+    api_host_override: Option<String>,
+    fields: Option<Vec<String>>,
+) -> Result<Vec<SophosEndpoint>, String> {
+    let started = std::time::Instant::now();
+    let dedup_strategy = load_settings().dedup_strategy;
+
+    // An override queries another tenant with the same token, under its own cache file
+    let mut cache_path = match &tenant_override {
+        Some(tenant) => get_tenant_cache_path(tenant),
+        None => get_cache_path(),
+    };
+
+    // Slim results leave the omitted fields as None, so they get their own cache too
+    let fields = fields.map(|fields| normalize_fields(&fields));
+    let mut query: Vec<(&str, String)> = Vec::new();
+    if let Some(fields) = &fields {
+        cache_path = fields_cache_path(&cache_path, fields);
+        query.push(("fields", fields.join(",")));
+    }
+
+    let is_tenant_override = tenant_override.is_some();
+    let tenant_id = tenant_override.unwrap_or(tenant_id);
+
+    // Check cache first
+    if let Some(cached_endpoints) = load_cached_data(&cache_path, &tenant_id) {
+        let endpoints = apply_dedup_strategy(cached_endpoints, dedup_strategy);
+        audit::record_fetch(&tenant_id, endpoints.len(), true, started.elapsed());
+        return Ok(endpoints);
+    }
+
+    let client = http_client()?;
+
+    // Prefer the host whoami reported over one built from the region string
+    let whoami_host = match (&api_host_override, is_tenant_override) {
+        (None, false) => api_host::data_region_host(&client, &access_token, &tenant_id).await,
+        _ => None,
+    };
+    let api_host = resolve_api_host(
+        &region,
+        allow_unknown_region.unwrap_or(false),
+        api_host_override.as_deref().or(whoami_host.as_deref()),
+    )?;
+    let base_url = endpoints_base_url(&region, Some(&api_host));
+    let all_endpoints = match fetch_endpoint_pages(&client, &base_url, &access_token, &tenant_id, &query, dedup_strategy).await {
+        Ok(endpoints) => endpoints,
+        // A token that's rejected by the data-region host usually means the tenant lives in
+        // another region. Ask whoami where, and retry there exactly once.
+        Err(error @ (SophosError::Api { status: 401 | 403, .. } | SophosError::RegionMismatch { .. }))
+            if api_host_override.is_none() && !is_tenant_override =>
+        {
+            let corrected_host = match fetch_whoami(&client, &access_token).await {
+                Ok(whoami) => whoami.api_hosts.and_then(|hosts| hosts.data_region),
+                Err(e) => {
+                    println!("   ⚠️  Whoami lookup failed: {}", e);
+                    None
+                }
+            };
+            let Some(corrected_host) = corrected_host.filter(|host| endpoints_base_url(&region, Some(host)) != base_url) else {
+                return Err(error.into());
+            };
+
+            println!("🌍 Configured region '{}' looks wrong, retrying against {}", region, corrected_host);
+            let endpoints = fetch_endpoint_pages(
+                &client,
+                &endpoints_base_url(&region, Some(&corrected_host)),
+                &access_token,
+                &tenant_id,
+                &query,
+                dedup_strategy,
+            )
+            .await?;
+            api_host::store_api_host(&tenant_id, &corrected_host);
+
+            match region_from_api_host(&corrected_host) {
+                Some(corrected_region) if load_settings().persist_region_correction => {
+                    if let Err(e) = persist_corrected_region(&tenant_id, &corrected_region) {
+                        println!("   ⚠️  Could not save corrected region: {}", e);
+                    }
+                }
+                Some(corrected_region) => println!(
+                    "   💡 Tenant data is in region '{}'; update your credentials or enable persist_region_correction",
+                    corrected_region
+                ),
+                None => {}
+            }
+
+            endpoints
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    // Save to cache for future use. `fields` only narrows each record, never the set, and
+    // has its own cache file, so this is always the full inventory.
+    save_cached_data(&cache_path, &all_endpoints, &tenant_id, CacheWriteMode::Replace);
     
     // Debug: Log sample endpoint structure from first endpoint
     if let Some(first_endpoint) = all_endpoints.first() {
         println!("Sample endpoint structure: {:#?}", first_endpoint);
     }
     
-    Ok(all_endpoints)
+    let endpoints = apply_dedup_strategy(all_endpoints, dedup_strategy);
+    audit::record_fetch(&tenant_id, endpoints.len(), false, started.elapsed());
+    Ok(endpoints)
+}
+
+/// Replaces cached endpoints with their updated versions by id and appends new ones.
+fn merge_endpoints(cached: Vec<SophosEndpoint>, updated: Vec<SophosEndpoint>) -> Vec<SophosEndpoint> {
+    let mut merged = cached;
+    let mut index_by_id: HashMap<String, usize> = merged
+        .iter()
+        .enumerate()
+        .map(|(index, endpoint)| (endpoint.id.clone(), index))
+        .collect();
+
+    for endpoint in updated {
+        match index_by_id.get(&endpoint.id) {
+            Some(&index) => merged[index] = endpoint,
+            None => {
+                index_by_id.insert(endpoint.id.clone(), merged.len());
+                merged.push(endpoint);
+            }
+        }
+    }
+
+    merged
+}
+
+/// Refreshes the cache by fetching only endpoints seen since it was written and merging
+/// them in by id. Falls back to a full fetch when there's no usable cache for this tenant
+/// or Sophos rejects the `lastSeenAfter` filter.
+///
+/// Endpoints that dropped off since the last sync keep their cached state until the next
+/// full fetch, since a `lastSeenAfter` query can't report them.
+#[tauri::command]
+async fn sync_sophos_endpoints(
+    access_token: String,
+    tenant_id: String,
+    region: String,
+    allow_unknown_region: Option<bool>,
+) -> Result<Vec<SophosEndpoint>, String> {
+    validate_region(&region, allow_unknown_region.unwrap_or(false))?;
+
+    let dedup_strategy = load_settings().dedup_strategy;
+    let cache_path = get_cache_path();
+    let client = http_client()?;
+    let api_root = api_host::resolve_api_root(
+        &client,
+        &access_token,
+        &tenant_id,
+        &region,
+        allow_unknown_region.unwrap_or(false),
+    )
+    .await?;
+    let base_url = endpoints_base_url(&region, Some(&api_root));
+
+    let cached = read_cache_file(&cache_path)
+        .ok()
+        .filter(|cached| {
+            cached.tenant_id == tenant_id
+                && cached.schema_version <= CACHE_SCHEMA_VERSION
+                && cached.checksum_matches() != Some(false)
+        });
+
+    let (endpoints, write_mode) = match cached {
+        Some(cached) => {
+            // Overlap the window slightly so clock skew can't hide an update
+            let since_timestamp = cached.timestamp.saturating_sub(INCREMENTAL_SYNC_OVERLAP_SECS);
+            let since = chrono::DateTime::from_timestamp(since_timestamp as i64, 0)
+                .ok_or("Cache timestamp is out of range")?
+                .to_rfc3339_opts(chrono::SecondsFormat::Secs, true);
+
+            println!("🔄 Incremental sync: fetching endpoints seen since {}", since);
+            let query = [("lastSeenAfter", since)];
+
+            match fetch_endpoint_pages(&client, &base_url, &access_token, &tenant_id, &query, dedup_strategy).await {
+                Ok(updated) => {
+                    println!("   ✅ {} endpoints updated since last sync", updated.len());
+                    // Already merged into the cache read above, so it's written as the whole inventory
+                    (merge_endpoints(cached.endpoints, updated), CacheWriteMode::Replace)
+                }
+                Err(SophosError::Api { status: 400, .. }) => {
+                    println!("   ⚠️  lastSeenAfter not supported, falling back to full fetch");
+                    let endpoints =
+                        fetch_endpoint_pages(&client, &base_url, &access_token, &tenant_id, &[], dedup_strategy).await?;
+                    (endpoints, CacheWriteMode::Replace)
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        None => {
+            println!("📂 No usable cache for incremental sync, doing a full fetch");
+            let endpoints =
+                fetch_endpoint_pages(&client, &base_url, &access_token, &tenant_id, &[], dedup_strategy).await?;
+            (endpoints, CacheWriteMode::Replace)
+        }
+    };
+
+    save_cached_data(&cache_path, &endpoints, &tenant_id, write_mode);
+
+    Ok(apply_dedup_strategy(endpoints, dedup_strategy))
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
   tauri::Builder::default()
     .plugin(tauri_plugin_http::init())
-    .invoke_handler(tauri::generate_handler![get_sophos_access_token, fetch_sophos_endpoints, clear_cache])
+    .manage(TokenState::default())
+    .manage(ClientState::default())
+    .invoke_handler(tauri::generate_handler![
+      get_sophos_access_token,
+      token::invalidate_token,
+      fetch_sophos_endpoints,
+      fetch_sophos_endpoints_with_stats,
+      sync_sophos_endpoints,
+      streaming::fetch_sophos_endpoints_streaming,
+      clear_cache,
+      cache_repair::repair_cache,
+      cache_repair::verify_cache_integrity,
+      reset_all_data,
+      get_storage_status,
+      check_storage_writable,
+      list_cache_files,
+      save_sophos_credentials,
+      load_sophos_credentials,
+      update_region,
+      credentials_source,
+      get_effective_api_host,
+      api_host::refresh_api_host,
+      parse_credentials_json,
+      merge_endpoints_into_cache,
+      get_credentials_age,
+      get_offline_endpoints,
+      get_online_endpoints,
+      find_endpoints_without_ip,
+      isolation::get_isolated_endpoints,
+      isolation::isolate_endpoints_matching,
+      filters::fetch_endpoints_since,
+      filters::find_endpoints_last_seen_before,
+      filters::fetch_unhealthy_endpoints,
+      filters::fetch_endpoints_in_group,
+      audit::get_fetch_history,
+      summary::get_cached_summary,
+      queries::get_endpoints_page,
+      queries::find_endpoint_by_hostname,
+      queries::get_canonical_endpoints,
+      queries::get_endpoint_rows,
+      queries::get_priority_triage_list,
+      fingerprint::get_endpoint_fingerprint,
+      dns::resolve_endpoint_dns,
+      views::save_view,
+      views::list_views,
+      views::delete_view,
+      views::apply_view,
+      views::export_view_csv,
+      services::find_endpoints_with_service_issues,
+      updates::find_outdated_agents,
+      stats::get_endpoint_stats,
+      stats::get_group_distribution,
+      stats::get_subnet_distribution,
+      stats::get_last_seen_buckets,
+      stats::get_os_version_distribution,
+      tenant::fetch_tenant_health,
+      tenant::fetch_tenant_info,
+      tenant::get_endpoint_count,
+      tenant::reconcile_health,
+      policies::fetch_endpoint_policies,
+      export::export_endpoints_xlsx,
+      export::export_endpoints_json,
+      export::fetch_and_export,
+      export::export_config_template,
+      backup::export_backup,
+      backup::import_backup,
+      settings::get_settings,
+      settings::update_settings,
+      settings::get_effective_settings,
+      settings::get_user_agent_suffix,
+      settings::set_user_agent_suffix,
+      ui_state::get_last_selection,
+      ui_state::set_last_selection,
+      migrations::check_data_migrations,
+      diagnostics::run_connectivity_diagnostics,
+      diagnostics::diagnose_secrets_file,
+      diagnostics::fetch_first_page,
+      diagnostics::estimate_fetch,
+      diagnostics::check_config_consistency,
+      diagnostics::simulate_fetch_error,
+      actions::perform_alert_action,
+      actions::delete_stale_endpoints,
+      actions::scan_all_endpoints,
+      details::fetch_endpoint_details_bulk,
+      timings::get_last_fetch_timings,
+      timings::get_last_pages_metadata,
+      snapshots::diff_last_refresh,
+      snapshots::find_group_changes,
+      compliance::find_endpoints_missing_product,
+      compliance::fetch_endpoint_applications,
+      compliance::compute_coverage,
+      partner::list_partner_tenants,
+      partner::fetch_all_tenants_endpoints
+    ])
     .setup(|app| {
+      APP_HANDLE.set(app.handle().clone()).ok();
       if cfg!(debug_assertions) {
         app.handle().plugin(
           tauri_plugin_log::Builder::default()
@@ -329,3 +2355,488 @@ pub fn run() {
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn credentials(client_id: &str, client_secret: &str, tenant_id: &str, region: &str) -> SophosCredentials {
+        SophosCredentials {
+            client_id: client_id.to_string(),
+            client_secret: client_secret.to_string(),
+            tenant_id: tenant_id.to_string(),
+            region: region.to_string(),
+            allow_unknown_region: false,
+            credentials_created_at: None,
+        }
+    }
+
+    #[test]
+    fn normalized_trims_fields_and_lowercases_region() {
+        let normalized = credentials(" client \n", "\tsecret ", "  Tenant-ABC  ", " US01 ")
+            .normalized()
+            .unwrap();
+
+        assert_eq!(normalized.client_id, "client");
+        assert_eq!(normalized.client_secret, "secret");
+        // Tenant ids are trimmed but keep their case
+        assert_eq!(normalized.tenant_id, "Tenant-ABC");
+        assert_eq!(normalized.region, "us01");
+    }
+
+    #[test]
+    fn normalized_rejects_empty_fields_by_name() {
+        for (field, creds) in [
+            ("client_id", credentials("  ", "secret", "tenant", "us01")),
+            ("client_secret", credentials("client", "", "tenant", "us01")),
+            ("tenant_id", credentials("client", "secret", "\t", "us01")),
+        ] {
+            let error = creds.normalized().unwrap_err();
+            assert!(error.contains(&format!("'{}'", field)), "{}: {}", field, error);
+        }
+    }
+
+    #[test]
+    fn user_agent_without_suffix_is_the_base_agent() {
+        let settings = settings::AppSettings::default();
+        assert_eq!(user_agent(&settings), USER_AGENT);
+        assert!(USER_AGENT.starts_with("shoreagents-sophos/"));
+    }
+
+    #[test]
+    fn user_agent_appends_a_valid_suffix() {
+        let settings = settings::AppSettings {
+            user_agent_suffix: Some("  acme-msp ".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(user_agent(&settings), format!("{} (acme-msp)", USER_AGENT));
+    }
+
+    #[test]
+    fn user_agent_ignores_blank_or_invalid_suffix() {
+        for suffix in ["   ", "bad)(suffix", "line\nbreak"] {
+            let settings = settings::AppSettings {
+                user_agent_suffix: Some(suffix.to_string()),
+                ..Default::default()
+            };
+            assert_eq!(user_agent(&settings), USER_AGENT, "suffix {:?}", suffix);
+        }
+    }
+
+    const NOW: u64 = 1_700_000_000;
+
+    #[test]
+    fn future_cache_timestamp_is_stale() {
+        let _data_dir = test_support::isolated_data_dir();
+
+        assert!(!is_cache_valid_at(NOW + 1, NOW));
+        assert!(!is_cache_valid_at(NOW + 86_400, NOW));
+        // Far future must not overflow either
+        assert!(!is_cache_valid_at(u64::MAX, NOW));
+    }
+
+    #[test]
+    fn cache_expires_at_cache_duration_hours() {
+        let _data_dir = test_support::isolated_data_dir();
+        assert_eq!(load_settings().cache_duration_hours, 1);
+
+        assert!(is_cache_valid_at(NOW, NOW));
+        assert!(is_cache_valid_at(NOW - 3599, NOW));
+        assert!(!is_cache_valid_at(NOW - 3600, NOW));
+        assert!(!is_cache_valid_at(0, NOW));
+    }
+
+    fn env(vars: &[(&str, &str)]) -> Option<SophosCredentials> {
+        credentials_from_vars(|name| {
+            vars.iter()
+                .find(|(key, _)| *key == name)
+                .map(|(_, value)| value.to_string())
+        })
+    }
+
+    const FULL_ENV: [(&str, &str); 4] = [
+        ("SOPHOS_CLIENT_ID", "env-client"),
+        ("SOPHOS_CLIENT_SECRET", "env-secret"),
+        ("SOPHOS_TENANT_ID", "env-tenant"),
+        ("SOPHOS_REGION", " US01 "),
+    ];
+
+    #[test]
+    fn credentials_from_env_only() {
+        let _data_dir = test_support::isolated_data_dir();
+
+        let (credentials, source) = resolve_credentials_with(env(&FULL_ENV));
+
+        assert_eq!(source, CredentialSource::Env);
+        let credentials = credentials.unwrap();
+        assert_eq!(credentials.client_id, "env-client");
+        assert_eq!(credentials.region, "us01");
+        assert!(!credentials.allow_unknown_region);
+    }
+
+    #[test]
+    fn credentials_from_file_only() {
+        let _data_dir = test_support::isolated_data_dir();
+        test_support::save_credentials("file-client", "file-tenant");
+
+        let (credentials, source) = resolve_credentials_with(env(&[]));
+
+        assert_eq!(source, CredentialSource::File);
+        assert_eq!(credentials.unwrap().client_id, "file-client");
+    }
+
+    #[test]
+    fn credentials_from_env_win_over_file() {
+        let _data_dir = test_support::isolated_data_dir();
+        test_support::save_credentials("file-client", "file-tenant");
+
+        let (credentials, source) = resolve_credentials_with(env(&FULL_ENV));
+
+        assert_eq!(source, CredentialSource::Env);
+        assert_eq!(credentials.unwrap().client_id, "env-client");
+    }
+
+    #[test]
+    fn partial_env_falls_through_to_file() {
+        let _data_dir = test_support::isolated_data_dir();
+        test_support::save_credentials("file-client", "file-tenant");
+
+        // Missing, then present but blank
+        for partial in [&FULL_ENV[..3], &[FULL_ENV[0], FULL_ENV[1], FULL_ENV[2], ("SOPHOS_REGION", "  ")][..]] {
+            let from_env = env(partial);
+            assert!(from_env.is_none());
+
+            let (credentials, source) = resolve_credentials_with(from_env);
+            assert_eq!(source, CredentialSource::File);
+            assert_eq!(credentials.unwrap().client_id, "file-client");
+        }
+    }
+
+    #[test]
+    fn no_credentials_anywhere() {
+        let _data_dir = test_support::isolated_data_dir();
+
+        let (credentials, source) = resolve_credentials_with(env(&FULL_ENV[..1]));
+
+        assert!(credentials.is_none());
+        assert_eq!(source, CredentialSource::None);
+    }
+
+    #[test]
+    fn allow_unknown_region_env_var_is_read() {
+        let mut vars = FULL_ENV.to_vec();
+        vars.push((ALLOW_UNKNOWN_REGION_ENV_VAR, "Yes"));
+        assert!(env(&vars).unwrap().allow_unknown_region);
+    }
+
+    #[test]
+    fn pinned_chain_is_not_app_managed() {
+        assert!(!is_app_managed_file(PINNED_CHAIN_FILE));
+        assert!(is_app_managed_file(SECRETS_FILE));
+        assert!(is_app_managed_file(CACHE_FILE));
+    }
+
+    fn endpoints(items: Vec<serde_json::Value>) -> Vec<SophosEndpoint> {
+        items.into_iter().map(|item| serde_json::from_value(item).unwrap()).collect()
+    }
+
+    #[tokio::test]
+    async fn merge_command_uses_the_shared_cache_write_path() {
+        let _data_dir = test_support::isolated_data_dir();
+        let cache_path = get_cache_path();
+        save_cached_data(
+            &cache_path,
+            &endpoints(vec![
+                test_support::endpoint_json("a", "ws-a", "2024-05-01T00:00:00Z"),
+                test_support::endpoint_json("b", "ws-b", "2024-05-01T00:00:00Z"),
+            ]),
+            "tenant",
+            CacheWriteMode::Replace,
+        );
+
+        let changed = merge_endpoints_into_cache(
+            endpoints(vec![
+                test_support::endpoint_json("b", "ws-b-renamed", "2024-05-02T00:00:00Z"),
+                test_support::endpoint_json("c", "ws-c", "2024-05-02T00:00:00Z"),
+            ]),
+            "tenant".to_string(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(changed, 2);
+
+        let cached = read_cache_file(&cache_path).unwrap();
+        let hostnames: Vec<_> = cached.endpoints.iter().map(|endpoint| endpoint.hostname.clone().unwrap()).collect();
+        assert_eq!(hostnames, ["ws-a", "ws-b-renamed", "ws-c"]);
+        assert_eq!(cached.checksum_matches(), Some(true));
+        // The previous inventory was snapshotted, which refresh alerts diff against
+        assert_eq!(read_cache_file(&snapshots::previous_snapshot_path(&cache_path)).unwrap().endpoints.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn merge_command_refuses_another_tenants_cache() {
+        let _data_dir = test_support::isolated_data_dir();
+        save_cached_data(&get_cache_path(), &[], "tenant", CacheWriteMode::Replace);
+
+        let result = merge_endpoints_into_cache(Vec::new(), "other-tenant".to_string()).await;
+
+        assert!(result.unwrap_err().contains("other-tenant"));
+    }
+
+    fn save_settings_json(settings: serde_json::Value) {
+        fs::write(get_app_data_dir().join(settings::SETTINGS_FILE), settings.to_string()).unwrap();
+    }
+
+    #[test]
+    fn token_url_uses_a_valid_override_only() {
+        let _data_dir = test_support::isolated_data_dir();
+        assert_eq!(token_url(), TOKEN_URL);
+
+        save_settings_json(serde_json::json!({ "token_endpoint_override": " http://127.0.0.1:9/token " }));
+        assert_eq!(token_url(), "http://127.0.0.1:9/token");
+
+        save_settings_json(serde_json::json!({ "token_endpoint_override": "ftp://127.0.0.1/token" }));
+        assert_eq!(token_url(), TOKEN_URL);
+    }
+
+    #[tokio::test]
+    async fn token_then_fetch_against_one_mock_server() {
+        let _data_dir = test_support::isolated_data_dir();
+        let token_body = serde_json::json!({ "access_token": "mock-token", "token_type": "bearer", "expires_in": 3600 });
+        let page_body = serde_json::json!({
+            "items": [test_support::endpoint_json("a", "ws-a", "2024-05-01T00:00:00Z")],
+            "pages": {},
+        });
+        let (url, server) =
+            test_support::mock_server(vec![(200, token_body.to_string()), (200, page_body.to_string())]).await;
+        save_settings_json(serde_json::json!({ "token_endpoint_override": format!("{}/oauth2/token", url) }));
+
+        let client = http_client().unwrap();
+        let token = client.get_token("client", "secret").await.unwrap();
+        let endpoints = fetch_endpoint_pages(
+            &client,
+            &endpoints_base_url("", Some(&url)),
+            &token.access_token,
+            "tenant",
+            &[],
+            DedupStrategy::ById,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(endpoints.len(), 1);
+        let requests = server.await.unwrap();
+        assert!(requests[0].starts_with("POST /oauth2/token "));
+        assert!(requests[0].contains("client_id=client"));
+        assert!(requests[1].starts_with("GET /endpoint/v1/endpoints?"));
+        assert_eq!(test_support::request_header(&requests[1], "authorization").as_deref(), Some("Bearer mock-token"));
+        assert_eq!(test_support::request_header(&requests[1], "x-tenant-id").as_deref(), Some("tenant"));
+    }
+
+    #[tokio::test]
+    async fn shared_client_sends_the_user_agent() {
+        let _data_dir = test_support::isolated_data_dir();
+        let (url, server) = test_support::mock_server(vec![(200, "{}".to_string())]).await;
+
+        let response = http_client().unwrap().get(&url).send().await.unwrap();
+        assert!(response.status().is_success());
+
+        let requests = server.await.unwrap();
+        assert_eq!(
+            test_support::request_header(&requests[0], "user-agent").as_deref(),
+            Some(USER_AGENT)
+        );
+    }
+
+    mod paging {
+        use super::*;
+        use crate::test_support::{
+            endpoint_json, isolated_data_dir, json_response, page, save_credentials, status, FakeClient,
+        };
+
+        const BASE_URL: &str = "https://api-eu01.central.sophos.com/endpoint/v1/endpoints";
+
+        async fn fetch(client: &FakeClient, strategy: DedupStrategy) -> Result<FetchResult, SophosError> {
+            fetch_endpoint_pages_with(client, BASE_URL, "initial-token", "tenant", &[], strategy, |_, _| {}).await
+        }
+
+        fn ids(endpoints: &[SophosEndpoint]) -> Vec<&str> {
+            endpoints.iter().map(|endpoint| endpoint.id.as_str()).collect()
+        }
+
+        #[tokio::test]
+        async fn total_comes_from_a_one_item_page() {
+            let client = FakeClient::with_pages(vec![json_response(serde_json::json!({
+                "items": [endpoint_json("a", "host-a", "2026-01-01T00:00:00Z")],
+                "pages": { "items": 1234 },
+            }))]);
+
+            let query = [("healthStatus", "bad".to_string())];
+            let total = fetch_endpoint_total(&client, BASE_URL, "token", "tenant", &query).await.unwrap();
+
+            assert_eq!(total, 1234);
+            let request = &client.requests()[0];
+            assert_eq!(request.param("pageSize"), Some("1"));
+            assert_eq!(request.param("pageTotal"), Some("true"));
+            assert_eq!(request.param("healthStatus"), Some("bad"));
+        }
+
+        #[tokio::test]
+        async fn detail_errors_keep_the_api_status() {
+            let client = FakeClient::with_responses(vec![
+                json_response(serde_json::json!({ "id": "a", "hostname": "host-a" })),
+                status(404),
+            ]);
+
+            let detail = fetch_endpoint_detail(&client, BASE_URL, "token", "tenant", "a").await.unwrap();
+            assert_eq!(detail["hostname"], "host-a");
+
+            let missing = fetch_endpoint_detail(&client, BASE_URL, "token", "tenant", "b").await;
+            assert!(matches!(missing, Err(SophosError::Api { status: 404, .. })));
+            let urls: Vec<String> = client.calls().into_iter().map(|call| call.url).collect();
+            assert_eq!(urls, [format!("{}/a", BASE_URL), format!("{}/b", BASE_URL)]);
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn follows_next_key_until_the_last_page() {
+            let _data_dir = isolated_data_dir();
+            let client = FakeClient::with_pages(vec![
+                page(vec![endpoint_json("a", "ws-a", "2024-05-01T00:00:00Z")], Some("key-1")),
+                page(vec![endpoint_json("b", "ws-b", "2024-05-01T00:00:00Z")], Some("key-2")),
+                page(vec![endpoint_json("c", "ws-c", "2024-05-01T00:00:00Z")], None),
+            ]);
+
+            let result = fetch(&client, DedupStrategy::ById).await.unwrap();
+
+            assert_eq!(ids(&result.endpoints), ["a", "b", "c"]);
+            assert_eq!(result.pages, 3);
+            assert_eq!(result.retries, 0);
+
+            let requests = client.requests();
+            assert_eq!(requests.len(), 3);
+            assert_eq!(requests[0].param("pageFromKey"), None);
+            assert_eq!(requests[0].param("pageTotal"), Some("true"));
+            assert_eq!(requests[1].param("pageFromKey"), Some("key-1"));
+            assert_eq!(requests[1].param("pageTotal"), None);
+            assert_eq!(requests[2].param("pageFromKey"), Some("key-2"));
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn retries_rate_limits_and_server_errors_with_backoff() {
+            let _data_dir = isolated_data_dir();
+            let client = FakeClient::with_pages(vec![
+                status(429),
+                status(503),
+                Err("connection reset".to_string()),
+                page(vec![endpoint_json("a", "ws-a", "2024-05-01T00:00:00Z")], None),
+            ]);
+
+            let started = tokio::time::Instant::now();
+            let result = fetch(&client, DedupStrategy::ById).await.unwrap();
+
+            assert_eq!(ids(&result.endpoints), ["a"]);
+            assert_eq!(result.retries, 3);
+            assert_eq!(result.rate_limited_count, 1);
+            assert_eq!(result.pages, 1);
+            // 1s + 2s + 4s of backoff
+            assert!(started.elapsed() >= std::time::Duration::from_millis(7000));
+
+            // Every attempt asked for the same first page
+            assert!(client.requests().iter().all(|request| request.param("pageTotal") == Some("true")));
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn gives_up_after_max_page_retries() {
+            let _data_dir = isolated_data_dir();
+            let client = FakeClient::with_pages(vec![status(500), status(500), status(500), status(500)]);
+
+            let error = fetch(&client, DedupStrategy::ById).await.unwrap_err();
+
+            assert!(matches!(error, SophosError::Api { status: 500, .. }), "{:?}", error);
+            assert_eq!(client.requests().len(), 4);
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn reauthenticates_once_when_the_token_is_rejected_mid_fetch() {
+            let _data_dir = isolated_data_dir();
+            save_credentials("client", "tenant");
+            let client = FakeClient::with_pages(vec![
+                page(vec![endpoint_json("a", "ws-a", "2024-05-01T00:00:00Z")], Some("key-1")),
+                status(401),
+                page(vec![endpoint_json("b", "ws-b", "2024-05-01T00:00:00Z")], None),
+            ]);
+
+            let result = fetch(&client, DedupStrategy::ById).await.unwrap();
+
+            assert_eq!(ids(&result.endpoints), ["a", "b"]);
+            assert_eq!(result.retries, 1);
+            assert_eq!(client.token_exchanges.load(std::sync::atomic::Ordering::SeqCst), 1);
+
+            let requests = client.requests();
+            assert_eq!(requests[1].access_token, "initial-token");
+            assert_eq!(requests[2].access_token, "token-1");
+            assert_eq!(requests[2].param("pageFromKey"), Some("key-1"));
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn a_second_rejected_token_fails_the_fetch() {
+            let _data_dir = isolated_data_dir();
+            save_credentials("client", "tenant");
+            let client = FakeClient::with_pages(vec![
+                page(vec![endpoint_json("a", "ws-a", "2024-05-01T00:00:00Z")], Some("key-1")),
+                status(401),
+                status(401),
+            ]);
+
+            let error = fetch(&client, DedupStrategy::ById).await.unwrap_err();
+
+            assert!(matches!(error, SophosError::Api { status: 401, .. }), "{:?}", error);
+            assert_eq!(client.token_exchanges.load(std::sync::atomic::Ordering::SeqCst), 1);
+        }
+
+        fn reenrolled_pages() -> Vec<Result<client::RawResponse, String>> {
+            vec![
+                page(
+                    vec![
+                        endpoint_json("old", "WS-042", "2024-04-01T00:00:00Z"),
+                        endpoint_json("other", "ws-043", "2024-05-01T00:00:00Z"),
+                    ],
+                    Some("key-1"),
+                ),
+                page(
+                    vec![
+                        // Repeated across a page boundary
+                        endpoint_json("other", "ws-043", "2024-05-01T00:00:00Z"),
+                        // Same machine re-enrolled under a new id
+                        endpoint_json("new", " ws-042 ", "2024-05-02T00:00:00Z"),
+                    ],
+                    None,
+                ),
+            ]
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn by_id_drops_repeated_ids_only() {
+            let _data_dir = isolated_data_dir();
+            let client = FakeClient::with_pages(reenrolled_pages());
+
+            let result = fetch(&client, DedupStrategy::ById).await.unwrap();
+            let endpoints = apply_dedup_strategy(result.endpoints, DedupStrategy::ById);
+
+            assert_eq!(ids(&endpoints), ["old", "other", "new"]);
+        }
+
+        #[tokio::test(start_paused = true)]
+        async fn by_hostname_keeps_the_latest_record_per_machine() {
+            let _data_dir = isolated_data_dir();
+            let client = FakeClient::with_pages(reenrolled_pages());
+
+            let result = fetch(&client, DedupStrategy::ByHostname).await.unwrap();
+            // Pure hostname mode doesn't drop repeated ids while paging
+            assert_eq!(result.endpoints.len(), 4);
+            let endpoints = apply_dedup_strategy(result.endpoints, DedupStrategy::ByHostname);
+
+            assert_eq!(ids(&endpoints), ["new", "other"]);
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+use crate::{
+    cache_file, endpoints_checksum, get_cache_path, get_secrets_path, write_secrets_file, SecretsFile,
+    SophosCredentials, SophosEndpoint, CACHE_SCHEMA_VERSION, DEFAULT_PROFILE, SECRETS_SCHEMA_VERSION,
+};
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum MigrationState {
+    Missing,
+    UpToDate,
+    Migrated,
+    Failed,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct FileMigration {
+    file: String,
+    found_version: Option<u32>,
+    current_version: u32,
+    state: MigrationState,
+    detail: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct MigrationStatus {
+    files: Vec<FileMigration>,
+    all_current: bool,
+}
+
+pub(crate) fn schema_version_of(value: &serde_json::Value, unversioned: u32) -> u32 {
+    value
+        .get("schema_version")
+        .and_then(|v| v.as_u64())
+        .map(|v| v as u32)
+        .unwrap_or(unversioned)
+}
+
+/// Parses the secrets file in any known layout, returning it in the current layout
+/// along with the version it was found in.
+///
+/// v1 was a bare credentials object (no `schema_version`); v2 wraps credentials in a
+/// named profile map. A v1 file becomes the `default` profile.
+pub(crate) fn parse_secrets(content: &str) -> Result<(SecretsFile, u32), String> {
+    let value: serde_json::Value = serde_json::from_str(content).map_err(|e| e.to_string())?;
+    let version = schema_version_of(&value, 1);
+
+    match version {
+        1 => {
+            let credentials: SophosCredentials = serde_json::from_value(value).map_err(|e| e.to_string())?;
+            let mut secrets = SecretsFile::default();
+            secrets.profiles.insert(DEFAULT_PROFILE.to_string(), credentials);
+            Ok((secrets, version))
+        }
+        SECRETS_SCHEMA_VERSION => {
+            let secrets: SecretsFile = serde_json::from_value(value).map_err(|e| e.to_string())?;
+            Ok((secrets, version))
+        }
+        _ => Err(format!(
+            "Unsupported secrets schema v{} (this version supports up to v{})",
+            version, SECRETS_SCHEMA_VERSION
+        )),
+    }
+}
+
+fn missing(path: &Path, current_version: u32) -> FileMigration {
+    FileMigration {
+        file: path.display().to_string(),
+        found_version: None,
+        current_version,
+        state: MigrationState::Missing,
+        detail: None,
+    }
+}
+
+fn failed(path: &Path, found_version: Option<u32>, current_version: u32, detail: String) -> FileMigration {
+    FileMigration {
+        file: path.display().to_string(),
+        found_version,
+        current_version,
+        state: MigrationState::Failed,
+        detail: Some(detail),
+    }
+}
+
+fn migrate_secrets() -> FileMigration {
+    let path = get_secrets_path();
+    if !path.exists() {
+        return missing(&path, SECRETS_SCHEMA_VERSION);
+    }
+
+    let content = match fs::read_to_string(&path) {
+        Ok(content) => content,
+        Err(e) => return failed(&path, None, SECRETS_SCHEMA_VERSION, format!("Failed to read file: {}", e)),
+    };
+
+    let (secrets, found_version) = match parse_secrets(&content) {
+        Ok(parsed) => parsed,
+        Err(e) => return failed(&path, None, SECRETS_SCHEMA_VERSION, e),
+    };
+
+    let state = if found_version < SECRETS_SCHEMA_VERSION {
+        if let Err(e) = write_secrets_file(&secrets) {
+            return failed(&path, Some(found_version), SECRETS_SCHEMA_VERSION, e);
+        }
+        MigrationState::Migrated
+    } else {
+        MigrationState::UpToDate
+    };
+
+    FileMigration {
+        file: path.display().to_string(),
+        found_version: Some(found_version),
+        current_version: SECRETS_SCHEMA_VERSION,
+        state,
+        detail: None,
+    }
+}
+
+fn migrate_cache() -> FileMigration {
+    let path = get_cache_path();
+    if !cache_file::exists(&path) {
+        return missing(&path, CACHE_SCHEMA_VERSION);
+    }
+
+    let value = match cache_file::read_text(&path)
+        .map_err(|e| format!("Failed to read file: {}", e))
+        .and_then(|content| serde_json::from_str::<serde_json::Value>(&content).map_err(|e| e.to_string()))
+    {
+        Ok(value) => value,
+        Err(e) => return failed(&path, None, CACHE_SCHEMA_VERSION, e),
+    };
+
+    // v0 caches predate the version field and v1 caches the checksum; the layout is
+    // otherwise unchanged
+    let found_version = schema_version_of(&value, 0);
+    if found_version > CACHE_SCHEMA_VERSION {
+        return failed(
+            &path,
+            Some(found_version),
+            CACHE_SCHEMA_VERSION,
+            "Cache was written by a newer version".to_string(),
+        );
+    }
+
+    let state = if found_version < CACHE_SCHEMA_VERSION {
+        let mut value = value;
+        if value.get("checksum").is_none_or(|checksum| checksum.is_null()) {
+            let endpoints = match serde_json::from_value::<Vec<SophosEndpoint>>(value["endpoints"].clone()) {
+                Ok(endpoints) => endpoints,
+                Err(e) => return failed(&path, Some(found_version), CACHE_SCHEMA_VERSION, e.to_string()),
+            };
+            value["checksum"] = serde_json::json!(endpoints_checksum(&endpoints));
+        }
+        value["schema_version"] = serde_json::json!(CACHE_SCHEMA_VERSION);
+        let written = serde_json::to_string_pretty(&value)
+            .map_err(|e| e.to_string())
+            .and_then(|json_content| cache_file::write_text(&path, &json_content).map_err(|e| e.to_string()));
+        if let Err(e) = written {
+            return failed(&path, Some(found_version), CACHE_SCHEMA_VERSION, e);
+        }
+        MigrationState::Migrated
+    } else {
+        MigrationState::UpToDate
+    };
+
+    FileMigration {
+        file: path.display().to_string(),
+        found_version: Some(found_version),
+        current_version: CACHE_SCHEMA_VERSION,
+        state,
+        detail: None,
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn check_data_migrations() -> Result<MigrationStatus, String> {
+    let files = vec![migrate_secrets(), migrate_cache()];
+    let all_current = files.iter().all(|file| file.state != MigrationState::Failed);
+
+    for file in &files {
+        if file.state == MigrationState::Migrated {
+            println!("🔄 Migrated {} to schema v{}", file.file, file.current_version);
+        }
+    }
+
+    Ok(MigrationStatus { files, all_current })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn v1_secrets_become_the_default_profile() {
+        let content = include_str!("../tests/fixtures/secrets_v1.json");
+
+        let (secrets, found_version) = parse_secrets(content).unwrap();
+
+        assert_eq!(found_version, 1);
+        assert_eq!(secrets.schema_version, SECRETS_SCHEMA_VERSION);
+        assert_eq!(secrets.schema_version, 2);
+        assert_eq!(secrets.active_profile, DEFAULT_PROFILE);
+        assert_eq!(secrets.profiles.len(), 1);
+
+        let credentials = &secrets.profiles[DEFAULT_PROFILE];
+        assert_eq!(credentials.client_id, "3f1c0a52-8d7e-4c1b-9a0e-5b2f6d4e7a10");
+        assert_eq!(credentials.client_secret, "v1-fixture-secret");
+        assert_eq!(credentials.tenant_id, "b7e2d9c4-1a3f-4e5d-8c6b-0f9a2e1d3c4b");
+        assert_eq!(credentials.region, "eu01");
+        assert!(!credentials.allow_unknown_region);
+        assert_eq!(credentials.credentials_created_at, None);
+    }
+
+    #[test]
+    fn v2_secrets_parse_unchanged() {
+        let content = include_str!("../tests/fixtures/secrets_v1.json");
+        let (migrated, _) = parse_secrets(content).unwrap();
+        let v2 = serde_json::to_string(&migrated).unwrap();
+
+        let (secrets, found_version) = parse_secrets(&v2).unwrap();
+
+        assert_eq!(found_version, SECRETS_SCHEMA_VERSION);
+        assert_eq!(secrets.active_profile, DEFAULT_PROFILE);
+        assert_eq!(secrets.profiles[DEFAULT_PROFILE].client_id, migrated.profiles[DEFAULT_PROFILE].client_id);
+    }
+
+    #[test]
+    fn cache_without_checksum_migrates_to_current_schema() {
+        let _data_dir = crate::test_support::isolated_data_dir();
+        let now = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        // v0 layout: no schema_version, no checksum
+        let old_cache = serde_json::json!({
+            "endpoints": [crate::test_support::endpoint_json("a", "ws-a", "2024-05-01T00:00:00Z")],
+            "timestamp": now,
+            "tenant_id": "tenant",
+        });
+        fs::write(get_cache_path(), old_cache.to_string()).unwrap();
+
+        let migration = migrate_cache();
+
+        assert_eq!(migration.state, MigrationState::Migrated);
+        assert_eq!(migration.found_version, Some(0));
+
+        let migrated = crate::read_cache_file(&get_cache_path()).unwrap();
+        assert_eq!(migrated.schema_version, CACHE_SCHEMA_VERSION);
+        assert_eq!(migrated.checksum_matches(), Some(true));
+        assert!(crate::load_cached_data(&get_cache_path(), "tenant").is_some());
+
+        // Running it again finds nothing to do
+        assert_eq!(migrate_cache().state, MigrationState::UpToDate);
+    }
+
+    #[test]
+    fn newer_secrets_schema_is_rejected() {
+        let content = r#"{"schema_version": 3, "active_profile": "default", "profiles": {}}"#;
+        assert!(parse_secrets(content).is_err());
+    }
+}
@@ -0,0 +1,139 @@
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+
+use crate::rate_limit::rate_limiter;
+use crate::settings::load_settings;
+use crate::{describe_api_error, describe_request_error};
+
+#[derive(Debug, Deserialize)]
+struct NumberedPage<T> {
+    items: Option<Vec<T>>,
+    pages: Option<serde_json::Value>,
+}
+
+/// Collects every item from an API that pages by number (policies, partner tenants),
+/// walking `page=1..pages.total`. Without a reported total, a short page is the last one.
+/// `headers` go with every request; `label` names the items in errors, e.g. "policies".
+pub(crate) async fn fetch_numbered_pages<T: DeserializeOwned>(
+    client: &reqwest::Client,
+    url: &str,
+    headers: &[(&str, String)],
+    label: &str,
+) -> Result<Vec<T>, String> {
+    let page_size = load_settings().page_size;
+
+    let mut items = Vec::new();
+    let mut page = 1u64;
+    loop {
+        rate_limiter().acquire().await;
+        let mut request = client
+            .get(url)
+            .query(&[("pageSize", page_size.to_string()), ("page", page.to_string())])
+            .query(&[("pageTotal", "true")])
+            .header("Accept", "application/json");
+        for (name, value) in headers {
+            request = request.header(*name, value);
+        }
+        let response = request
+            .send()
+            .await
+            .map_err(|e| format!("Request for {} failed on page {}: {}", label, page, describe_request_error(&e)))?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let error_text = response.text().await.unwrap_or_default();
+            return Err(format!(
+                "Request for {} failed on page {} ({}): {}",
+                label,
+                page,
+                status,
+                describe_api_error(&error_text)
+            ));
+        }
+
+        let body: NumberedPage<T> = response
+            .json()
+            .await
+            .map_err(|e| format!("Failed to parse {} on page {}: {}", label, page, e))?;
+
+        let page_items = body.items.unwrap_or_default();
+        let page_len = page_items.len();
+        items.extend(page_items);
+
+        let total_pages = body
+            .pages
+            .as_ref()
+            .and_then(|pages| pages.get("total"))
+            .and_then(|total| total.as_u64());
+        let has_more = match total_pages {
+            Some(total) => page < total,
+            None => page_len >= page_size as usize,
+        };
+        if page_len == 0 || !has_more {
+            break;
+        }
+        page += 1;
+    }
+
+    Ok(items)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{isolated_data_dir, mock_server, request_header};
+
+    #[derive(Debug, Deserialize)]
+    struct Item {
+        id: String,
+    }
+
+    fn page_body(ids: &[&str], total: Option<u64>) -> String {
+        let items: Vec<_> = ids.iter().map(|id| serde_json::json!({ "id": id })).collect();
+        let pages = total.map_or(serde_json::json!({}), |total| serde_json::json!({ "total": total }));
+        serde_json::json!({ "items": items, "pages": pages }).to_string()
+    }
+
+    #[tokio::test]
+    async fn walks_pages_up_to_the_reported_total() {
+        let _data_dir = isolated_data_dir();
+        let (url, server) =
+            mock_server(vec![(200, page_body(&["a", "b"], Some(2))), (200, page_body(&["c"], Some(2)))]).await;
+
+        let items: Vec<Item> =
+            fetch_numbered_pages(&reqwest::Client::new(), &url, &[("X-Partner-ID", "partner".to_string())], "items")
+                .await
+                .unwrap();
+
+        assert_eq!(items.iter().map(|item| item.id.as_str()).collect::<Vec<_>>(), ["a", "b", "c"]);
+        let requests = server.await.unwrap();
+        assert!(requests[0].contains("page=1"));
+        assert!(requests[1].contains("page=2"));
+        assert_eq!(request_header(&requests[1], "x-partner-id").as_deref(), Some("partner"));
+    }
+
+    #[tokio::test]
+    async fn stops_on_a_short_page_without_a_total() {
+        let _data_dir = isolated_data_dir();
+        // The default page size is 100, so one item is a short page
+        let (url, server) = mock_server(vec![(200, page_body(&["a"], None))]).await;
+
+        let items: Vec<Item> = fetch_numbered_pages(&reqwest::Client::new(), &url, &[], "items").await.unwrap();
+
+        assert_eq!(items.len(), 1);
+        assert_eq!(server.await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn api_errors_name_the_label_and_page() {
+        let _data_dir = isolated_data_dir();
+        let (url, _server) = mock_server(vec![(403, r#"{"message":"forbidden"}"#.to_string())]).await;
+
+        let error = fetch_numbered_pages::<Item>(&reqwest::Client::new(), &url, &[], "partner tenants")
+            .await
+            .unwrap_err();
+
+        assert!(error.contains("partner tenants"), "{}", error);
+        assert!(error.contains("page 1"), "{}", error);
+    }
+}
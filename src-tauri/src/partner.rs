@@ -0,0 +1,144 @@
+use futures::stream::{self, StreamExt};
+use serde::{Deserialize, Serialize};
+
+use crate::paging::fetch_numbered_pages;
+use crate::settings::load_settings;
+use crate::{
+    api_host_url, apply_dedup_strategy, fetch_endpoint_pages, get_tenant_cache_path, http_client, load_cached_data,
+    save_cached_data, CacheWriteMode, SophosEndpoint,
+};
+
+const PARTNER_TENANTS_URL: &str = "https://api.central.sophos.com/partner/v1/tenants";
+
+/// Tenants fetched at once in the all-tenants aggregation; each still pages sequentially
+/// and goes through the shared rate limiter.
+const TENANT_FETCH_CONCURRENCY: usize = 3;
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct PartnerTenant {
+    id: String,
+    name: Option<String>,
+    #[serde(rename = "showAs")]
+    show_as: Option<String>,
+    #[serde(rename = "dataRegion")]
+    data_region: Option<String>,
+    /// Where the tenant's data lives; authoritative over `dataRegion`
+    #[serde(rename = "apiHost")]
+    api_host: Option<String>,
+}
+
+impl PartnerTenant {
+    fn display_name(&self) -> Option<String> {
+        self.show_as.clone().or_else(|| self.name.clone())
+    }
+
+    fn api_host(&self) -> Option<String> {
+        match (&self.api_host, &self.data_region) {
+            (Some(api_host), _) if !api_host.trim().is_empty() => Some(api_host_url("", Some(api_host))),
+            (_, Some(region)) if !region.trim().is_empty() => Some(api_host_url(&region.trim().to_lowercase(), None)),
+            _ => None,
+        }
+    }
+}
+
+/// Every tenant the partner manages.
+async fn fetch_partner_tenants(
+    client: &reqwest::Client,
+    access_token: &str,
+    partner_id: &str,
+) -> Result<Vec<PartnerTenant>, String> {
+    let headers = [
+        ("Authorization", format!("Bearer {}", access_token)),
+        ("X-Partner-ID", partner_id.to_string()),
+    ];
+    fetch_numbered_pages(client, PARTNER_TENANTS_URL, &headers, "partner tenants").await
+}
+
+/// Tenants managed by the partner, with the API host each one's data lives on.
+#[tauri::command]
+pub(crate) async fn list_partner_tenants(access_token: String, partner_id: String) -> Result<Vec<PartnerTenant>, String> {
+    let tenants = fetch_partner_tenants(&http_client()?, &access_token, partner_id.trim()).await?;
+    println!("🏢 Partner manages {} tenants", tenants.len());
+    Ok(tenants)
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TenantEndpoints {
+    tenant_id: String,
+    tenant_name: Option<String>,
+    endpoints: Vec<SophosEndpoint>,
+    /// Why this tenant's endpoints couldn't be fetched; the other tenants are unaffected
+    error: Option<String>,
+}
+
+/// One tenant's endpoints from its own API host, through its per-tenant cache.
+async fn fetch_tenant_endpoints(
+    client: &reqwest::Client,
+    access_token: &str,
+    tenant: &PartnerTenant,
+) -> Result<Vec<SophosEndpoint>, String> {
+    let dedup_strategy = load_settings().dedup_strategy;
+    let cache_path = get_tenant_cache_path(&tenant.id);
+    if let Some(cached_endpoints) = load_cached_data(&cache_path, &tenant.id) {
+        return Ok(apply_dedup_strategy(cached_endpoints, dedup_strategy));
+    }
+
+    let api_host = tenant
+        .api_host()
+        .ok_or_else(|| format!("Tenant {} has no API host or data region", tenant.id))?;
+    let base_url = format!("{}/endpoint/v1/endpoints", api_host);
+
+    let endpoints = fetch_endpoint_pages(client, &base_url, access_token, &tenant.id, &[], dedup_strategy).await?;
+    save_cached_data(&cache_path, &endpoints, &tenant.id, CacheWriteMode::Replace);
+    Ok(apply_dedup_strategy(endpoints, dedup_strategy))
+}
+
+/// Endpoints for every tenant the partner manages, each fetched from that tenant's own
+/// API host and cached per tenant. A tenant that fails is reported with its error rather
+/// than failing the whole aggregation. Sorted by tenant name.
+#[tauri::command]
+pub(crate) async fn fetch_all_tenants_endpoints(
+    access_token: String,
+    partner_id: String,
+) -> Result<Vec<TenantEndpoints>, String> {
+    let client = http_client()?;
+    let tenants = fetch_partner_tenants(&client, &access_token, partner_id.trim()).await?;
+    println!("🏢 Fetching endpoints for {} partner tenants", tenants.len());
+
+    let mut results: Vec<TenantEndpoints> = stream::iter(tenants)
+        .map(|tenant| {
+            let client = client.clone();
+            let access_token = access_token.clone();
+            async move {
+                let result = fetch_tenant_endpoints(&client, &access_token, &tenant).await;
+                if let Err(e) = &result {
+                    println!("   ⚠️  Tenant {}: {}", tenant.id, e);
+                }
+                let (endpoints, error) = match result {
+                    Ok(endpoints) => (endpoints, None),
+                    Err(e) => (Vec::new(), Some(e)),
+                };
+                TenantEndpoints {
+                    tenant_name: tenant.display_name(),
+                    tenant_id: tenant.id,
+                    endpoints,
+                    error,
+                }
+            }
+        })
+        .buffer_unordered(TENANT_FETCH_CONCURRENCY)
+        .collect()
+        .await;
+
+    results.sort_by(|a, b| a.tenant_name.cmp(&b.tenant_name).then_with(|| a.tenant_id.cmp(&b.tenant_id)));
+
+    let failed = results.iter().filter(|tenant| tenant.error.is_some()).count();
+    let total: usize = results.iter().map(|tenant| tenant.endpoints.len()).sum();
+    println!(
+        "✅ {} endpoints across {} tenants ({} failed)",
+        total,
+        results.len(),
+        failed
+    );
+    Ok(results)
+}
@@ -0,0 +1,78 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::api_host::resolve_api_root;
+use crate::paging::fetch_numbered_pages;
+use crate::{http_client, require_credentials};
+
+/// Policies are edited by hand in Sophos Central, so a short-lived copy is fine.
+const POLICY_CACHE_TTL: Duration = Duration::from_secs(600);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct SophosPolicy {
+    id: String,
+    name: Option<String>,
+    #[serde(rename(deserialize = "type"), alias = "policy_type")]
+    policy_type: Option<String>,
+    enabled: Option<bool>,
+    /// Lower numbers win; the base policy has no priority
+    priority: Option<u32>,
+}
+
+struct PolicyCache {
+    tenant_id: String,
+    fetched_at: Instant,
+    policies: Vec<SophosPolicy>,
+}
+
+static POLICY_CACHE: Mutex<Option<PolicyCache>> = Mutex::new(None);
+
+fn cached_policies(tenant_id: &str) -> Option<Vec<SophosPolicy>> {
+    let cache = POLICY_CACHE.lock().ok()?;
+    cache
+        .as_ref()
+        .filter(|cache| cache.tenant_id == tenant_id && cache.fetched_at.elapsed() < POLICY_CACHE_TTL)
+        .map(|cache| cache.policies.clone())
+}
+
+fn store_policies(tenant_id: &str, policies: &[SophosPolicy]) {
+    if let Ok(mut cache) = POLICY_CACHE.lock() {
+        *cache = Some(PolicyCache {
+            tenant_id: tenant_id.to_string(),
+            fetched_at: Instant::now(),
+            policies: policies.to_vec(),
+        });
+    }
+}
+
+/// Every endpoint policy in the tenant. The policies API pages by number, so this walks
+/// `page=1..pages.total`. Cached in memory for ten minutes.
+#[tauri::command]
+pub(crate) async fn fetch_endpoint_policies(access_token: String) -> Result<Vec<SophosPolicy>, String> {
+    let credentials = require_credentials()?;
+    if let Some(policies) = cached_policies(&credentials.tenant_id) {
+        println!("📦 Using cached policies ({} policies)", policies.len());
+        return Ok(policies);
+    }
+
+    let client = http_client()?;
+    let api_root = resolve_api_root(
+        &client,
+        &access_token,
+        &credentials.tenant_id,
+        &credentials.region,
+        credentials.allow_unknown_region,
+    )
+    .await?;
+    let url = format!("{}/endpoint/v1/policies", api_root);
+    let headers = [
+        ("Authorization", format!("Bearer {}", &access_token)),
+        ("X-Tenant-ID", credentials.tenant_id.clone()),
+    ];
+    let policies: Vec<SophosPolicy> = fetch_numbered_pages(&client, &url, &headers, "policies").await?;
+
+    println!("📜 Fetched {} policies", policies.len());
+    store_policies(&credentials.tenant_id, &policies);
+    Ok(policies)
+}
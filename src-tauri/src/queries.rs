@@ -0,0 +1,152 @@
+use std::cmp::Reverse;
+
+use serde::{Deserialize, Serialize};
+
+use crate::{keep_latest_by, load_cached_endpoints, SophosEndpoint};
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct EndpointPage {
+    total: usize,
+    offset: usize,
+    limit: usize,
+    endpoints: Vec<SophosEndpoint>,
+}
+
+/// Sorts by `hostname` (A-Z, case-insensitive), `last_seen` (most recent first) or
+/// `health` (worst first). Ties keep cache order.
+fn sort_endpoints(endpoints: &mut [SophosEndpoint], sort_by: &str) -> Result<(), String> {
+    match sort_by {
+        "hostname" => {
+            endpoints.sort_by_key(|endpoint| endpoint.hostname.as_ref().map(|h| h.to_lowercase()))
+        }
+        "last_seen" => endpoints.sort_by_key(|endpoint| Reverse(endpoint.last_seen_at())),
+        "health" => endpoints.sort_by_key(|endpoint| Reverse(endpoint.health_severity())),
+        _ => {
+            return Err(format!(
+                "Unsupported sort field '{}'. Use hostname, last_seen or health.",
+                sort_by
+            ))
+        }
+    }
+    Ok(())
+}
+
+/// Returns one slice of the cached inventory, so large tables don't ship every endpoint
+/// over IPC at once.
+#[tauri::command]
+pub(crate) async fn get_endpoints_page(
+    offset: usize,
+    limit: usize,
+    sort_by: Option<String>,
+) -> Result<EndpointPage, String> {
+    let mut endpoints = load_cached_endpoints()?;
+
+    if let Some(sort_by) = sort_by.as_deref() {
+        sort_endpoints(&mut endpoints, sort_by)?;
+    }
+
+    let total = endpoints.len();
+    let endpoints = endpoints.into_iter().skip(offset).take(limit).collect();
+
+    Ok(EndpointPage {
+        total,
+        offset,
+        limit,
+        endpoints,
+    })
+}
+
+/// Exact, case-insensitive hostname lookup in the cached inventory. With duplicate
+/// records the most recently seen one wins.
+#[tauri::command]
+pub(crate) async fn find_endpoint_by_hostname(hostname: String) -> Result<Option<SophosEndpoint>, String> {
+    let hostname = hostname.trim().to_lowercase();
+
+    let found = load_cached_endpoints()?
+        .into_iter()
+        .filter(|endpoint| endpoint.hostname_key().is_some_and(|key| key == hostname))
+        .max_by_key(|endpoint| endpoint.last_seen_at());
+
+    Ok(found)
+}
+
+/// One record per machine from the cached inventory, keyed by `"hostname"` (case-insensitive)
+/// or `"id"`, keeping the most recently seen record. Most recently seen first.
+#[tauri::command]
+pub(crate) async fn get_canonical_endpoints(dedup_by: String) -> Result<Vec<SophosEndpoint>, String> {
+    let endpoints = load_cached_endpoints()?;
+
+    let mut canonical = match dedup_by.trim().to_lowercase().as_str() {
+        "hostname" => keep_latest_by(endpoints, SophosEndpoint::hostname_key),
+        "id" => keep_latest_by(endpoints, |endpoint| Some(endpoint.id.clone())),
+        other => return Err(format!("Unsupported dedup key '{}'. Use 'hostname' or 'id'.", other)),
+    };
+    canonical.sort_by_key(|endpoint| Reverse(endpoint.last_seen_at()));
+
+    Ok(canonical)
+}
+
+/// An endpoint flattened into table columns, with the nested `os`, `health` and `group`
+/// JSON already dug out.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct EndpointRow {
+    id: String,
+    hostname: Option<String>,
+    #[serde(rename = "type")]
+    endpoint_type: Option<String>,
+    online: Option<bool>,
+    os_platform: Option<String>,
+    os_name: String,
+    /// `good`, `suspicious`, `bad` or `unknown`
+    health: String,
+    group_name: Option<String>,
+    primary_ipv4: Option<String>,
+    last_seen: Option<String>,
+}
+
+impl From<&SophosEndpoint> for EndpointRow {
+    fn from(endpoint: &SophosEndpoint) -> Self {
+        EndpointRow {
+            id: endpoint.id.clone(),
+            hostname: endpoint.hostname.clone(),
+            endpoint_type: endpoint.endpoint_type.as_ref().map(|endpoint_type| endpoint_type.to_string()),
+            online: endpoint.online,
+            os_platform: endpoint
+                .os
+                .as_ref()
+                .and_then(|os| os.get("platform"))
+                .and_then(|value| value.as_str())
+                .map(|platform| platform.to_string()),
+            os_name: endpoint.os_name(),
+            health: endpoint.health_status(),
+            group_name: endpoint.group_name(),
+            primary_ipv4: endpoint.primary_ipv4().map(|ip| ip.to_string()),
+            last_seen: endpoint.last_seen.clone(),
+        }
+    }
+}
+
+/// The cached inventory as flat table rows, in cache order.
+#[tauri::command]
+pub(crate) async fn get_endpoint_rows() -> Result<Vec<EndpointRow>, String> {
+    let endpoints = load_cached_endpoints()?;
+    Ok(endpoints.iter().map(EndpointRow::from).collect())
+}
+
+/// Cached endpoints that are online with `suspicious` or `bad` health, the ones that can
+/// be acted on right now. Bad before suspicious, then by hostname; unknown health is left out.
+#[tauri::command]
+pub(crate) async fn get_priority_triage_list() -> Result<Vec<SophosEndpoint>, String> {
+    let mut triage: Vec<SophosEndpoint> = load_cached_endpoints()?
+        .into_iter()
+        .filter(|endpoint| endpoint.online == Some(true) && endpoint.health_severity() >= 2)
+        .collect();
+
+    triage.sort_by_key(|endpoint| {
+        let hostname = endpoint.hostname.as_ref().map(|hostname| hostname.to_lowercase());
+        (Reverse(endpoint.health_severity()), hostname.is_none(), hostname)
+    });
+
+    println!("🚨 {} online endpoints need attention", triage.len());
+    Ok(triage)
+}
@@ -0,0 +1,79 @@
+use std::sync::OnceLock;
+use std::time::{Duration, Instant};
+
+use crate::settings::load_settings;
+
+static RATE_LIMITER: OnceLock<RateLimiter> = OnceLock::new();
+
+/// Seconds of traffic the bucket can absorb at once before requests are spaced out.
+const BURST_WINDOW_SECS: f64 = 10.0;
+
+struct Bucket {
+    per_minute: u32,
+    tokens: f64,
+    refilled_at: Instant,
+}
+
+impl Bucket {
+    fn capacity(&self) -> f64 {
+        (self.per_minute as f64 / 60.0 * BURST_WINDOW_SECS).max(1.0)
+    }
+
+    fn refill(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_minute as f64 / 60.0).min(self.capacity());
+        self.refilled_at = now;
+    }
+}
+
+/// Token bucket shared by every outbound Sophos request, so concurrent commands
+/// (refresh, bulk actions, token exchange) stay under one combined budget.
+pub(crate) struct RateLimiter {
+    bucket: tokio::sync::Mutex<Bucket>,
+}
+
+impl RateLimiter {
+    fn new(per_minute: u32) -> Self {
+        let mut bucket = Bucket {
+            per_minute,
+            tokens: 0.0,
+            refilled_at: Instant::now(),
+        };
+        bucket.tokens = bucket.capacity();
+        RateLimiter {
+            bucket: tokio::sync::Mutex::new(bucket),
+        }
+    }
+
+    /// Applies a new rate; 0 turns limiting off.
+    pub(crate) async fn set_rate(&self, per_minute: u32) {
+        let mut bucket = self.bucket.lock().await;
+        bucket.refill();
+        bucket.per_minute = per_minute;
+        bucket.tokens = bucket.tokens.min(bucket.capacity());
+    }
+
+    /// Waits until a request may be sent.
+    pub(crate) async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut bucket = self.bucket.lock().await;
+                if bucket.per_minute == 0 {
+                    return;
+                }
+                bucket.refill();
+                if bucket.tokens >= 1.0 {
+                    bucket.tokens -= 1.0;
+                    return;
+                }
+                Duration::from_secs_f64((1.0 - bucket.tokens) * 60.0 / bucket.per_minute as f64)
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+}
+
+pub(crate) fn rate_limiter() -> &'static RateLimiter {
+    RATE_LIMITER.get_or_init(|| RateLimiter::new(load_settings().max_requests_per_minute))
+}
@@ -0,0 +1,73 @@
+use crate::{fetch_with_saved_credentials, SophosEndpoint};
+
+/// Status Sophos reports for a healthy agent service.
+const RUNNING: &str = "running";
+
+/// One service entry's name and status. Entries come either as objects
+/// (`{"name": ..., "status": ...}`) or as plain strings keyed by service name.
+fn service_entry(name: Option<&str>, entry: &serde_json::Value) -> Option<(String, String)> {
+    let (name, status) = match entry {
+        serde_json::Value::Object(details) => (
+            details.get("name").and_then(|value| value.as_str()).or(name),
+            details.get("status").and_then(|value| value.as_str()),
+        ),
+        serde_json::Value::String(status) => (name, Some(status.as_str())),
+        _ => (name, None),
+    };
+
+    Some((name?.trim().to_string(), status.unwrap_or("unknown").trim().to_string()))
+}
+
+/// Names of agent services in `health.services` that aren't running. Understands the
+/// documented `{"status": ..., "serviceDetails": [...]}` shape as well as a bare array or
+/// a map of service name to status. Anything else yields no issues.
+fn stopped_services(endpoint: &SophosEndpoint) -> Vec<String> {
+    let Some(services) = endpoint.health.as_ref().and_then(|health| health.get("services")) else {
+        return Vec::new();
+    };
+
+    let entries: Vec<(String, String)> = match services {
+        serde_json::Value::Array(details) => details.iter().filter_map(|entry| service_entry(None, entry)).collect(),
+        serde_json::Value::Object(map) => match map.get("serviceDetails") {
+            Some(serde_json::Value::Array(details)) => {
+                details.iter().filter_map(|entry| service_entry(None, entry)).collect()
+            }
+            // The summary `status` sits next to the details; it isn't a service
+            _ => map
+                .iter()
+                .filter(|(name, _)| name.as_str() != "status")
+                .filter_map(|(name, entry)| service_entry(Some(name), entry))
+                .collect(),
+        },
+        _ => Vec::new(),
+    };
+
+    let mut stopped: Vec<String> = entries
+        .into_iter()
+        .filter(|(name, status)| !name.is_empty() && !status.eq_ignore_ascii_case(RUNNING))
+        .map(|(name, _)| name)
+        .collect();
+    stopped.sort();
+    stopped.dedup();
+    stopped
+}
+
+/// Endpoints with at least one agent service not running, with the names of those
+/// services. Catches degraded agents whose overall health still reads `good`.
+#[tauri::command]
+pub(crate) async fn find_endpoints_with_service_issues(
+    access_token: String,
+) -> Result<Vec<(SophosEndpoint, Vec<String>)>, String> {
+    let endpoints = fetch_with_saved_credentials(access_token).await?;
+
+    let issues: Vec<(SophosEndpoint, Vec<String>)> = endpoints
+        .into_iter()
+        .filter_map(|endpoint| {
+            let stopped = stopped_services(&endpoint);
+            (!stopped.is_empty()).then_some((endpoint, stopped))
+        })
+        .collect();
+
+    println!("🩺 {} endpoints have agent services not running", issues.len());
+    Ok(issues)
+}
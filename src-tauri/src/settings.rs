@@ -0,0 +1,388 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::get_app_data_dir;
+use crate::rate_limit::rate_limiter;
+
+pub(crate) const SETTINGS_FILE: &str = "sophos_settings.json";
+
+/// Prefix for environment overrides, e.g. `SOPHOS_PAGE_SIZE=50`.
+const ENV_PREFIX: &str = "SOPHOS_";
+
+const MAX_USER_AGENT_SUFFIX_LEN: usize = 64;
+
+/// Upper bound for `page_delay_ms`; longer pauses make a large fetch take hours.
+pub(crate) const MAX_PAGE_DELAY_MS: u64 = 10_000;
+
+/// How duplicate endpoint records are collapsed after fetching.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+#[allow(clippy::enum_variant_names)]
+pub(crate) enum DedupStrategy {
+    /// Drop repeated Sophos ids only (original behavior)
+    #[default]
+    ById,
+    /// One record per hostname, keeping the most recently seen one
+    ByHostname,
+    /// Drop repeated ids, then collapse re-enrolled machines by hostname
+    ByIdThenHostname,
+}
+
+/// How the endpoint list is paged.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum PaginationMode {
+    /// Follow `pages.nextKey` with `pageFromKey` (original behavior)
+    #[default]
+    Key,
+    /// Request numbered pages with `page`, stopping at `pages.total`
+    Offset,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub(crate) struct AppSettings {
+    pub(crate) dedup_strategy: DedupStrategy,
+    /// When false, endpoint data is never written to or read from disk
+    pub(crate) cache_enabled: bool,
+    /// Save the region whoami reports back to the credentials after a region mismatch
+    pub(crate) persist_region_correction: bool,
+    /// Trust only the Sophos CA chain for outbound requests instead of the system roots
+    pub(crate) pin_sophos_cert: bool,
+    /// Combined budget for all outbound Sophos requests; 0 disables throttling
+    pub(crate) max_requests_per_minute: u32,
+    /// How long a cached inventory is served before refetching
+    pub(crate) cache_duration_hours: u64,
+    /// Endpoints requested per page when paginating the inventory
+    pub(crate) page_size: u32,
+    /// Store endpoint caches gzip-compressed (`.json.gz`)
+    pub(crate) compress_cache: bool,
+    /// Appended to the user agent to identify the organization, e.g. `acme-msp`
+    pub(crate) user_agent_suffix: Option<String>,
+    /// Re-authenticate this many seconds before the access token expires. Capped at half
+    /// the token's lifetime; see `CachedToken::is_valid_for`.
+    pub(crate) token_expiry_margin_seconds: u64,
+    /// Pause between endpoint pages, up to `MAX_PAGE_DELAY_MS`; 0 disables the delay
+    pub(crate) page_delay_ms: u64,
+    pub(crate) pagination_mode: PaginationMode,
+    /// Recommend rotating API credentials once they've been in use this many days; 0 disables
+    pub(crate) credentials_rotation_warning_days: u64,
+    /// Token endpoint to use instead of the Sophos identity host, e.g. a mock server
+    pub(crate) token_endpoint_override: Option<String>,
+    /// Retries per page after a 429, a 5xx or a dropped connection, with exponential
+    /// backoff; 0 fails on the first error
+    pub(crate) max_page_retries: u32,
+    /// Log every page of a fetch; otherwise large fetches log only every tenth page
+    /// past the first ten, plus the final summary
+    pub(crate) verbose_pagination_log: bool,
+}
+
+impl Default for AppSettings {
+    fn default() -> Self {
+        AppSettings {
+            dedup_strategy: DedupStrategy::default(),
+            cache_enabled: true,
+            persist_region_correction: false,
+            pin_sophos_cert: false,
+            max_requests_per_minute: 600,
+            cache_duration_hours: 1,
+            page_size: 100,
+            compress_cache: false,
+            user_agent_suffix: None,
+            token_expiry_margin_seconds: 60,
+            page_delay_ms: 100,
+            pagination_mode: PaginationMode::default(),
+            credentials_rotation_warning_days: 365,
+            token_endpoint_override: None,
+            max_page_retries: 3,
+            verbose_pagination_log: false,
+        }
+    }
+}
+
+fn get_settings_path() -> std::path::PathBuf {
+    get_app_data_dir().join(SETTINGS_FILE)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum SettingSource {
+    Default,
+    File,
+    Env,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct EffectiveSetting {
+    key: String,
+    value: serde_json::Value,
+    source: SettingSource,
+    env_var: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct EffectiveSettings {
+    path: String,
+    settings: Vec<EffectiveSetting>,
+}
+
+fn env_var_name(key: &str) -> String {
+    format!("{}{}", ENV_PREFIX, key.to_uppercase())
+}
+
+fn settings_from_map(map: &serde_json::Map<String, serde_json::Value>) -> Option<AppSettings> {
+    serde_json::from_value(serde_json::Value::Object(map.clone())).ok()
+}
+
+fn read_settings_file() -> Option<serde_json::Map<String, serde_json::Value>> {
+    let settings_path = get_settings_path();
+
+    if !settings_path.exists() {
+        return None;
+    }
+
+    let content = match fs::read_to_string(&settings_path) {
+        Ok(content) => content,
+        Err(e) => {
+            println!("❌ Failed to read settings, using defaults: {}", e);
+            return None;
+        }
+    };
+
+    let file_values = match serde_json::from_str::<serde_json::Map<String, serde_json::Value>>(&content) {
+        Ok(values) => values,
+        Err(e) => {
+            println!("❌ Failed to parse settings, using defaults: {}", e);
+            return None;
+        }
+    };
+
+    // A file that doesn't match the schema is ignored as a whole, as before
+    if let Err(e) = serde_json::from_value::<AppSettings>(serde_json::Value::Object(file_values.clone())) {
+        println!("❌ Failed to parse settings, using defaults: {}", e);
+        return None;
+    }
+
+    Some(file_values)
+}
+
+/// Resolves every setting as default, then settings file, then `SOPHOS_*` environment
+/// variable, recording where each value came from.
+fn resolve_settings() -> (AppSettings, BTreeMap<String, SettingSource>) {
+    let serde_json::Value::Object(mut values) =
+        serde_json::to_value(AppSettings::default()).unwrap_or_default()
+    else {
+        return (AppSettings::default(), BTreeMap::new());
+    };
+    let mut sources: BTreeMap<String, SettingSource> =
+        values.keys().map(|key| (key.clone(), SettingSource::Default)).collect();
+
+    if let Some(file_values) = read_settings_file() {
+        for (key, value) in file_values {
+            if let Some(source) = sources.get_mut(&key) {
+                *source = SettingSource::File;
+                values.insert(key, value);
+            }
+        }
+    }
+
+    let keys: Vec<String> = sources.keys().cloned().collect();
+    for key in keys {
+        let env_var = env_var_name(&key);
+        let Ok(raw) = std::env::var(&env_var) else {
+            continue;
+        };
+
+        // Numbers and booleans parse as JSON; anything else is taken as a plain string
+        let value = serde_json::from_str(&raw).unwrap_or(serde_json::Value::String(raw));
+        let mut candidate = values.clone();
+        candidate.insert(key.clone(), value);
+        if settings_from_map(&candidate).is_some() {
+            values = candidate;
+            sources.insert(key, SettingSource::Env);
+        } else {
+            println!("❌ Ignoring invalid value in {}", env_var);
+        }
+    }
+
+    let settings = settings_from_map(&values).unwrap_or_default();
+    (settings, sources)
+}
+
+/// Loads the effective settings, falling back to defaults when the file is missing or unreadable.
+pub(crate) fn load_settings() -> AppSettings {
+    resolve_settings().0
+}
+
+fn save_settings(settings: &impl Serialize) -> Result<(), String> {
+    let json_content = serde_json::to_string_pretty(settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+
+    fs::write(get_settings_path(), json_content)
+        .map_err(|e| format!("Failed to save settings: {}", e))
+}
+
+#[tauri::command]
+pub(crate) async fn get_settings() -> Result<AppSettings, String> {
+    Ok(load_settings())
+}
+
+/// Accepts short suffixes of letters, digits, spaces and `-_.:/@+`, which are safe in a
+/// header value and can't break out of the parentheses they're wrapped in.
+pub(crate) fn validate_user_agent_suffix(suffix: &str) -> Result<(), String> {
+    if suffix.len() > MAX_USER_AGENT_SUFFIX_LEN {
+        return Err(format!(
+            "User agent suffix is {} characters; the maximum is {}",
+            suffix.len(),
+            MAX_USER_AGENT_SUFFIX_LEN
+        ));
+    }
+
+    if let Some(invalid) = suffix
+        .chars()
+        .find(|c| !(c.is_ascii_alphanumeric() || " -_.:/@+".contains(*c)))
+    {
+        return Err(format!("User agent suffix contains an unsupported character '{}'", invalid));
+    }
+
+    Ok(())
+}
+
+/// Accepts an absolute `http` or `https` URL with a host.
+pub(crate) fn validate_token_endpoint(url: &str) -> Result<(), String> {
+    let parsed = reqwest::Url::parse(url).map_err(|e| format!("Token endpoint '{}' is not a valid URL: {}", url, e))?;
+    if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_none() {
+        return Err(format!("Token endpoint '{}' must be an http(s) URL with a host", url));
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub(crate) async fn update_settings(settings: AppSettings) -> Result<AppSettings, String> {
+    if let Some(suffix) = &settings.user_agent_suffix {
+        validate_user_agent_suffix(suffix.trim())?;
+    }
+    if let Some(url) = settings.token_endpoint_override.as_deref().map(str::trim).filter(|url| !url.is_empty()) {
+        validate_token_endpoint(url)?;
+    }
+    if settings.page_delay_ms > MAX_PAGE_DELAY_MS {
+        return Err(format!(
+            "Page delay must be between 0 and {} ms, got {}",
+            MAX_PAGE_DELAY_MS, settings.page_delay_ms
+        ));
+    }
+    save_settings(&settings)?;
+    rate_limiter().set_rate(settings.max_requests_per_minute).await;
+    println!("⚙️  Settings saved");
+    Ok(settings)
+}
+
+#[tauri::command]
+pub(crate) async fn get_effective_settings() -> Result<EffectiveSettings, String> {
+    let (settings, sources) = resolve_settings();
+    let serde_json::Value::Object(values) =
+        serde_json::to_value(&settings).map_err(|e| format!("Failed to serialize settings: {}", e))?
+    else {
+        return Err("Settings did not serialize to an object".to_string());
+    };
+
+    let settings = values
+        .into_iter()
+        .map(|(key, value)| EffectiveSetting {
+            source: sources.get(&key).copied().unwrap_or(SettingSource::Default),
+            env_var: env_var_name(&key),
+            key,
+            value,
+        })
+        .collect();
+
+    Ok(EffectiveSettings {
+        path: get_settings_path().display().to_string(),
+        settings,
+    })
+}
+
+#[tauri::command]
+pub(crate) async fn get_user_agent_suffix() -> Result<Option<String>, String> {
+    Ok(load_settings().user_agent_suffix)
+}
+
+/// Sets or clears (`None` or blank) the user agent suffix. The shared client picks it up
+/// on the next request.
+#[tauri::command]
+pub(crate) async fn set_user_agent_suffix(suffix: Option<String>) -> Result<Option<String>, String> {
+    let suffix = suffix
+        .map(|suffix| suffix.trim().to_string())
+        .filter(|suffix| !suffix.is_empty());
+    if let Some(suffix) = &suffix {
+        validate_user_agent_suffix(suffix)?;
+    }
+
+    // Edit only this key in the file; starting from load_settings() would persist any
+    // SOPHOS_* environment overrides as if they had been saved
+    let mut file_values = read_settings_file().unwrap_or_default();
+    match &suffix {
+        Some(suffix) => file_values.insert("user_agent_suffix".to_string(), suffix.clone().into()),
+        None => file_values.remove("user_agent_suffix"),
+    };
+    save_settings(&file_values)?;
+
+    println!("⚙️  User agent suffix set to {:?}", suffix);
+    Ok(suffix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::isolated_data_dir;
+
+    #[test]
+    fn token_endpoint_accepts_http_and_https_urls() {
+        for url in [
+            "https://id.sophos.com/api/v2/oauth2/token",
+            "http://127.0.0.1:8080/token",
+            "http://localhost/oauth2/token?tenant=test",
+        ] {
+            assert!(validate_token_endpoint(url).is_ok(), "{}", url);
+        }
+    }
+
+    #[test]
+    fn token_endpoint_rejects_other_urls() {
+        for url in [
+            "",
+            "id.sophos.com/api/v2/oauth2/token",
+            "/oauth2/token",
+            "ftp://id.sophos.com/token",
+            "file:///etc/passwd",
+            "https://",
+            "not a url",
+        ] {
+            assert!(validate_token_endpoint(url).is_err(), "{:?}", url);
+        }
+    }
+
+    #[tokio::test]
+    async fn user_agent_suffix_does_not_persist_env_overrides() {
+        let _data_dir = isolated_data_dir();
+        fs::write(get_settings_path(), r#"{"page_size": 250}"#).unwrap();
+        // Only changes pagination logging, so other tests running meanwhile are unaffected
+        std::env::set_var("SOPHOS_VERBOSE_PAGINATION_LOG", "true");
+        assert!(load_settings().verbose_pagination_log);
+
+        let result = set_user_agent_suffix(Some(" ci-runner ".to_string())).await;
+        std::env::remove_var("SOPHOS_VERBOSE_PAGINATION_LOG");
+        assert_eq!(result.unwrap().as_deref(), Some("ci-runner"));
+
+        let file_values = read_settings_file().unwrap();
+        assert!(!file_values.contains_key("verbose_pagination_log"));
+        assert_eq!(file_values["page_size"], 250);
+        assert_eq!(file_values["user_agent_suffix"], "ci-runner");
+
+        set_user_agent_suffix(None).await.unwrap();
+        let file_values = read_settings_file().unwrap();
+        assert!(!file_values.contains_key("user_agent_suffix"));
+        assert_eq!(file_values.len(), 1);
+    }
+}
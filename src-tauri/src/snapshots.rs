@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::{cache_file, get_cache_path, read_cache_file, SophosEndpoint};
+
+/// Where the cache's previous contents are kept, e.g. `sophos_cache.prev.json`.
+pub(crate) fn previous_snapshot_path(cache_path: &Path) -> PathBuf {
+    cache_path.with_extension("prev.json")
+}
+
+/// Copies the current cache aside before it's overwritten, so the next refresh can be
+/// compared against it.
+pub(crate) fn snapshot_previous(cache_path: &Path) {
+    if !cache_file::exists(cache_path) {
+        return;
+    }
+
+    let copied = cache_file::read_text(cache_path)
+        .and_then(|content| cache_file::write_text(&previous_snapshot_path(cache_path), &content));
+    if let Err(e) = copied {
+        println!("❌ Failed to keep previous snapshot: {}", e);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct InventoryDiff {
+    added: Vec<SophosEndpoint>,
+    removed: Vec<SophosEndpoint>,
+    /// (id, old health, new health)
+    pub(crate) health_changed: Vec<(String, String, String)>,
+    /// (id, was online, is online)
+    pub(crate) online_changed: Vec<(String, bool, bool)>,
+}
+
+pub(crate) fn diff_inventories(previous: Vec<SophosEndpoint>, current: Vec<SophosEndpoint>) -> InventoryDiff {
+    let mut previous_by_id: HashMap<String, SophosEndpoint> = previous
+        .into_iter()
+        .map(|endpoint| (endpoint.id.clone(), endpoint))
+        .collect();
+
+    let mut diff = InventoryDiff {
+        added: Vec::new(),
+        removed: Vec::new(),
+        health_changed: Vec::new(),
+        online_changed: Vec::new(),
+    };
+
+    for endpoint in current {
+        let Some(old) = previous_by_id.remove(&endpoint.id) else {
+            diff.added.push(endpoint);
+            continue;
+        };
+
+        let (old_health, new_health) = (old.health_status(), endpoint.health_status());
+        if old_health != new_health {
+            diff.health_changed.push((endpoint.id.clone(), old_health, new_health));
+        }
+
+        let (was_online, is_online) = (old.online == Some(true), endpoint.online == Some(true));
+        if was_online != is_online {
+            diff.online_changed.push((endpoint.id.clone(), was_online, is_online));
+        }
+    }
+
+    // Whatever wasn't matched by id is gone from the current inventory
+    diff.removed = previous_by_id.into_values().collect();
+    diff.removed.sort_by(|a, b| a.id.cmp(&b.id));
+
+    diff
+}
+
+/// The previous and current cached inventories, checked to belong to the same tenant.
+fn load_previous_and_current() -> Result<(Vec<SophosEndpoint>, Vec<SophosEndpoint>), String> {
+    let cache_path = get_cache_path();
+    let previous_path = previous_snapshot_path(&cache_path);
+
+    if !cache_file::exists(&previous_path) {
+        return Err("No previous snapshot yet. Refresh the inventory at least twice.".to_string());
+    }
+
+    let current = read_cache_file(&cache_path)?;
+    let previous = read_cache_file(&previous_path)?;
+
+    if previous.tenant_id != current.tenant_id {
+        return Err("Previous snapshot is for a different tenant".to_string());
+    }
+
+    Ok((previous.endpoints, current.endpoints))
+}
+
+#[tauri::command]
+pub(crate) async fn diff_last_refresh() -> Result<InventoryDiff, String> {
+    let (previous, current) = load_previous_and_current()?;
+    Ok(diff_inventories(previous, current))
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct GroupChange {
+    id: String,
+    hostname: Option<String>,
+    /// Group name, or its id when Sophos sent no name; `None` when ungrouped
+    old_group: Option<String>,
+    new_group: Option<String>,
+}
+
+/// Endpoints present in both the previous and current snapshot whose group differs.
+/// Groups are compared by id where both sides have one, so a renamed group isn't a move.
+#[tauri::command]
+pub(crate) async fn find_group_changes() -> Result<Vec<GroupChange>, String> {
+    let (previous, current) = load_previous_and_current()?;
+    let previous_by_id: HashMap<String, SophosEndpoint> = previous
+        .into_iter()
+        .map(|endpoint| (endpoint.id.clone(), endpoint))
+        .collect();
+
+    let mut changes: Vec<GroupChange> = current
+        .into_iter()
+        .filter_map(|endpoint| {
+            let old = previous_by_id.get(&endpoint.id)?;
+            let moved = match (old.group_id(), endpoint.group_id()) {
+                (Some(old_id), Some(new_id)) => old_id != new_id,
+                _ => old.group_name() != endpoint.group_name() || old.group_id() != endpoint.group_id(),
+            };
+            moved.then(|| GroupChange {
+                old_group: old.group_name().or_else(|| old.group_id()),
+                new_group: endpoint.group_name().or_else(|| endpoint.group_id()),
+                id: endpoint.id,
+                hostname: endpoint.hostname,
+            })
+        })
+        .collect();
+    changes.sort_by(|a, b| a.id.cmp(&b.id));
+
+    Ok(changes)
+}
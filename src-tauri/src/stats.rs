@@ -0,0 +1,301 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::net::Ipv4Addr;
+
+use crate::{load_cached_endpoints, SophosEndpoint};
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct EndpointStats {
+    pub(crate) total: usize,
+    pub(crate) online: usize,
+    pub(crate) offline: usize,
+    pub(crate) by_os: BTreeMap<String, usize>,
+    pub(crate) by_health: BTreeMap<String, usize>,
+    pub(crate) by_type: BTreeMap<String, usize>,
+}
+
+pub(crate) fn compute_endpoint_stats(endpoints: &[SophosEndpoint]) -> EndpointStats {
+    let mut stats = EndpointStats {
+        total: endpoints.len(),
+        online: 0,
+        offline: 0,
+        by_os: BTreeMap::new(),
+        by_health: BTreeMap::new(),
+        by_type: BTreeMap::new(),
+    };
+
+    for endpoint in endpoints {
+        if endpoint.online == Some(true) {
+            stats.online += 1;
+        } else {
+            stats.offline += 1;
+        }
+
+        *stats.by_os.entry(endpoint.os_name()).or_insert(0) += 1;
+        *stats.by_health.entry(endpoint.health_status()).or_insert(0) += 1;
+
+        let endpoint_type = endpoint
+            .endpoint_type
+            .as_ref()
+            .map_or_else(|| "unknown".to_string(), |endpoint_type| endpoint_type.to_string());
+        *stats.by_type.entry(endpoint_type).or_insert(0) += 1;
+    }
+
+    stats
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct GroupCount {
+    group_name: String,
+    group_id: Option<String>,
+    count: usize,
+}
+
+const UNGROUPED: &str = "(ungrouped)";
+
+fn group_distribution(endpoints: &[SophosEndpoint]) -> Vec<GroupCount> {
+    // Key by id where available so two groups sharing a name stay separate
+    let mut groups: BTreeMap<(Option<String>, String), usize> = BTreeMap::new();
+
+    for endpoint in endpoints {
+        let group_id = endpoint.group_id();
+        let group_name = endpoint
+            .group_name()
+            .or_else(|| group_id.clone())
+            .unwrap_or_else(|| UNGROUPED.to_string());
+        *groups.entry((group_id, group_name)).or_insert(0) += 1;
+    }
+
+    let mut distribution: Vec<GroupCount> = groups
+        .into_iter()
+        .map(|((group_id, group_name), count)| GroupCount { group_name, group_id, count })
+        .collect();
+
+    distribution.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.group_name.cmp(&b.group_name)));
+    distribution
+}
+
+#[tauri::command]
+pub(crate) async fn get_group_distribution() -> Result<Vec<GroupCount>, String> {
+    let endpoints = load_cached_endpoints()?;
+    Ok(group_distribution(&endpoints))
+}
+
+#[tauri::command]
+pub(crate) async fn get_endpoint_stats() -> Result<EndpointStats, String> {
+    let endpoints = load_cached_endpoints()?;
+    Ok(compute_endpoint_stats(&endpoints))
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct SubnetMember {
+    id: String,
+    hostname: Option<String>,
+    ip: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct SubnetGroup {
+    subnet: String,
+    count: usize,
+    members: Vec<SubnetMember>,
+}
+
+/// Groups endpoints by IPv4 /24. An endpoint with addresses in several subnets is listed
+/// under each of them, but only once per subnet.
+fn subnet_distribution(endpoints: &[SophosEndpoint]) -> Vec<SubnetGroup> {
+    let mut subnets: BTreeMap<Ipv4Addr, Vec<SubnetMember>> = BTreeMap::new();
+
+    for endpoint in endpoints {
+        for address in endpoint.ipv4_addresses.iter().flatten() {
+            let Ok(ip) = address.trim().parse::<Ipv4Addr>() else {
+                continue;
+            };
+            let network = Ipv4Addr::from(u32::from(ip) & 0xFFFF_FF00);
+            let members = subnets.entry(network).or_default();
+            if members.iter().any(|member| member.id == endpoint.id) {
+                continue;
+            }
+            members.push(SubnetMember {
+                id: endpoint.id.clone(),
+                hostname: endpoint.hostname.clone(),
+                ip: ip.to_string(),
+            });
+        }
+    }
+
+    subnets
+        .into_iter()
+        .map(|(network, members)| SubnetGroup {
+            subnet: format!("{}/24", network),
+            count: members.len(),
+            members,
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub(crate) async fn get_subnet_distribution() -> Result<Vec<SubnetGroup>, String> {
+    let endpoints = load_cached_endpoints()?;
+    Ok(subnet_distribution(&endpoints))
+}
+
+const LAST_SEEN_BUCKETS: &[&str] = &["<1h", "1-24h", "1-7d", "7-30d", ">30d", "never"];
+
+fn last_seen_bucket(endpoint: &SophosEndpoint, now: chrono::DateTime<chrono::Utc>) -> &'static str {
+    let Some(last_seen) = endpoint.last_seen_at() else {
+        return "never";
+    };
+
+    // Timestamps slightly in the future (clock skew) count as just seen
+    let age = now.signed_duration_since(last_seen);
+    if age < chrono::Duration::hours(1) {
+        "<1h"
+    } else if age < chrono::Duration::hours(24) {
+        "1-24h"
+    } else if age < chrono::Duration::days(7) {
+        "1-7d"
+    } else if age < chrono::Duration::days(30) {
+        "7-30d"
+    } else {
+        ">30d"
+    }
+}
+
+/// Counts endpoints by how long ago they were last seen. Missing or unparseable
+/// `lastSeen` values count as `never`. Every bucket is present, even when empty.
+#[tauri::command]
+pub(crate) async fn get_last_seen_buckets() -> Result<HashMap<String, u32>, String> {
+    let endpoints = load_cached_endpoints()?;
+    let now = chrono::Utc::now();
+
+    let mut buckets: HashMap<String, u32> = LAST_SEEN_BUCKETS
+        .iter()
+        .map(|bucket| (bucket.to_string(), 0))
+        .collect();
+    for endpoint in &endpoints {
+        *buckets.entry(last_seen_bucket(endpoint, now).to_string()).or_insert(0) += 1;
+    }
+
+    Ok(buckets)
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct OsVersionCount {
+    version: String,
+    count: usize,
+}
+
+const UNKNOWN_OS_VERSION: &str = "Unknown";
+
+/// Windows 10/11 feature updates by build number. Windows 11 still reports major version 10.
+const WINDOWS_RELEASES: &[(u64, &str, &str)] = &[
+    (10240, "Windows 10", "1507"),
+    (10586, "Windows 10", "1511"),
+    (14393, "Windows 10", "1607"),
+    (15063, "Windows 10", "1703"),
+    (16299, "Windows 10", "1709"),
+    (17134, "Windows 10", "1803"),
+    (17763, "Windows 10", "1809"),
+    (18362, "Windows 10", "1903"),
+    (18363, "Windows 10", "1909"),
+    (19041, "Windows 10", "2004"),
+    (19042, "Windows 10", "20H2"),
+    (19043, "Windows 10", "21H1"),
+    (19044, "Windows 10", "21H2"),
+    (19045, "Windows 10", "22H2"),
+    (22000, "Windows 11", "21H2"),
+    (22621, "Windows 11", "22H2"),
+    (22631, "Windows 11", "23H2"),
+    (26100, "Windows 11", "24H2"),
+];
+
+/// Sophos sends version parts as numbers on some platforms and strings on others.
+fn os_number(os: &serde_json::Value, key: &str) -> Option<u64> {
+    match os.get(key)? {
+        serde_json::Value::Number(number) => number.as_u64(),
+        serde_json::Value::String(text) => text.trim().parse().ok(),
+        _ => None,
+    }
+}
+
+fn os_text<'a>(os: &'a serde_json::Value, key: &str) -> Option<&'a str> {
+    os.get(key)
+        .and_then(|value| value.as_str())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+}
+
+fn windows_version_label(os: &serde_json::Value) -> Option<String> {
+    let name = os_text(os, "name");
+    let is_server = os.get("isServer").and_then(|value| value.as_bool()).unwrap_or(false)
+        || name.is_some_and(|name| name.contains("Server"));
+
+    // Server releases are identified well enough by name ("Windows Server 2019 Standard")
+    if is_server {
+        return name.map(str::to_string);
+    }
+
+    let major = os_number(os, "majorVersion");
+    let minor = os_number(os, "minorVersion");
+    let build = os_number(os, "build");
+
+    if let Some(build) = build {
+        if let Some((_, product, release)) = WINDOWS_RELEASES.iter().find(|(known, _, _)| *known == build) {
+            return Some(format!("{} {}", product, release));
+        }
+        if build >= 22000 {
+            return Some("Windows 11".to_string());
+        }
+    }
+
+    match (major, minor) {
+        (Some(10), _) => Some("Windows 10".to_string()),
+        (Some(6), Some(3)) => Some("Windows 8.1".to_string()),
+        (Some(6), Some(2)) => Some("Windows 8".to_string()),
+        (Some(6), Some(1)) => Some("Windows 7".to_string()),
+        _ => name.map(str::to_string),
+    }
+}
+
+/// A patch-planning label for the endpoint's OS, e.g. "Windows 11 23H2" or "macOS 14".
+/// Linux and anything unrecognised fall back to the name Sophos reports.
+fn os_version_label(os: Option<&serde_json::Value>) -> String {
+    let Some(os) = os.filter(|os| os.is_object()) else {
+        return UNKNOWN_OS_VERSION.to_string();
+    };
+
+    let platform = os_text(os, "platform").unwrap_or_default().to_lowercase();
+    let label = match platform.as_str() {
+        "windows" => windows_version_label(os),
+        "macos" | "mac" | "darwin" => os_number(os, "majorVersion")
+            .map(|major| format!("macOS {}", major))
+            .or_else(|| os_text(os, "name").map(str::to_string)),
+        _ => os_text(os, "name").map(str::to_string).or_else(|| {
+            os_number(os, "majorVersion")
+                .filter(|_| !platform.is_empty())
+                .map(|major| format!("{} {}", platform, major))
+        }),
+    };
+
+    label.unwrap_or_else(|| UNKNOWN_OS_VERSION.to_string())
+}
+
+/// Counts cached endpoints per OS version, most common first.
+#[tauri::command]
+pub(crate) async fn get_os_version_distribution() -> Result<Vec<OsVersionCount>, String> {
+    let endpoints = load_cached_endpoints()?;
+
+    let mut versions: BTreeMap<String, usize> = BTreeMap::new();
+    for endpoint in &endpoints {
+        *versions.entry(os_version_label(endpoint.os.as_ref())).or_insert(0) += 1;
+    }
+
+    let mut distribution: Vec<OsVersionCount> = versions
+        .into_iter()
+        .map(|(version, count)| OsVersionCount { version, count })
+        .collect();
+    distribution.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.version.cmp(&b.version)));
+
+    Ok(distribution)
+}
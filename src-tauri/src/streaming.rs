@@ -0,0 +1,80 @@
+use serde::{Deserialize, Serialize};
+use tauri::Emitter;
+
+use crate::api_host::resolve_endpoints_base_url;
+use crate::settings::load_settings;
+use crate::{
+    apply_dedup_strategy, fetch_endpoint_pages_with, get_cache_path, http_client, load_cached_data,
+    require_credentials, save_cached_data, validate_region, CacheWriteMode, SophosEndpoint,
+};
+
+const CHUNK_EVENT: &str = "sophos-endpoints-chunk";
+const COMPLETE_EVENT: &str = "sophos-endpoints-complete";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct EndpointsChunk {
+    page: u32,
+    endpoints: Vec<SophosEndpoint>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct EndpointsComplete {
+    total: usize,
+    pages: u32,
+    from_cache: bool,
+}
+
+/// Like `fetch_sophos_endpoints`, but sends each page as a `sophos-endpoints-chunk` event
+/// while paginating and finishes with `sophos-endpoints-complete`, so the UI can render
+/// progressively. Chunks are deduplicated by id only; the cache gets the complete set.
+/// Returns the number of endpoints fetched.
+#[tauri::command]
+pub(crate) async fn fetch_sophos_endpoints_streaming(
+    app: tauri::AppHandle,
+    access_token: String,
+) -> Result<usize, String> {
+    let credentials = require_credentials()?;
+    let dedup_strategy = load_settings().dedup_strategy;
+    let cache_path = get_cache_path();
+
+    if let Some(cached_endpoints) = load_cached_data(&cache_path, &credentials.tenant_id) {
+        let endpoints = apply_dedup_strategy(cached_endpoints, dedup_strategy);
+        let total = endpoints.len();
+        app.emit(CHUNK_EVENT, EndpointsChunk { page: 1, endpoints }).ok();
+        app.emit(COMPLETE_EVENT, EndpointsComplete { total, pages: 1, from_cache: true })
+            .ok();
+        return Ok(total);
+    }
+
+    validate_region(&credentials.region, credentials.allow_unknown_region)?;
+    let client = http_client()?;
+    let base_url = resolve_endpoints_base_url(&client, &access_token, &credentials).await?;
+
+    let result = fetch_endpoint_pages_with(
+        &client,
+        &base_url,
+        &access_token,
+        &credentials.tenant_id,
+        &[],
+        dedup_strategy,
+        |page, endpoints| {
+            app.emit(
+                CHUNK_EVENT,
+                EndpointsChunk {
+                    page,
+                    endpoints: endpoints.to_vec(),
+                },
+            )
+            .ok();
+        },
+    )
+    .await?;
+
+    save_cached_data(&cache_path, &result.endpoints, &credentials.tenant_id, CacheWriteMode::Replace);
+
+    let pages = result.pages;
+    let total = apply_dedup_strategy(result.endpoints, dedup_strategy).len();
+    app.emit(COMPLETE_EVENT, EndpointsComplete { total, pages, from_cache: false })
+        .ok();
+    Ok(total)
+}
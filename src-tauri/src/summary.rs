@@ -0,0 +1,69 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::stats::{compute_endpoint_stats, EndpointStats};
+use crate::{get_app_data_dir, safe_tenant_id, SophosEndpoint};
+
+pub(crate) const SUMMARY_PREFIX: &str = "sophos_summary";
+
+/// Headline numbers for one tenant, saved next to its cache so the dashboard can show
+/// them on launch without loading the inventory.
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedSummary {
+    tenant_id: String,
+    computed_at: String,
+    stats: EndpointStats,
+}
+
+fn get_summary_path(tenant_id: &str) -> std::path::PathBuf {
+    get_app_data_dir().join(format!("{}_{}.json", SUMMARY_PREFIX, safe_tenant_id(tenant_id)))
+}
+
+/// Recomputes and saves the tenant's summary. Called whenever its full inventory cache is
+/// rewritten; failures are only logged since the summary can always be rebuilt.
+pub(crate) fn write_summary(tenant_id: &str, endpoints: &[SophosEndpoint]) {
+    let summary = CachedSummary {
+        tenant_id: tenant_id.to_string(),
+        computed_at: chrono::Utc::now().to_rfc3339(),
+        stats: compute_endpoint_stats(endpoints),
+    };
+
+    let result = serde_json::to_string_pretty(&summary)
+        .map_err(|e| e.to_string())
+        .and_then(|json_content| fs::write(get_summary_path(tenant_id), json_content).map_err(|e| e.to_string()));
+    if let Err(e) = result {
+        println!("❌ Failed to save endpoint summary: {}", e);
+    }
+}
+
+/// Deletes every saved summary, returning how many were removed.
+pub(crate) fn remove_all_summaries() -> Result<usize, String> {
+    let mut removed = 0;
+    if let Ok(entries) = fs::read_dir(get_app_data_dir()) {
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if name.starts_with(SUMMARY_PREFIX) && name.ends_with(".json") {
+                fs::remove_file(entry.path()).map_err(|e| format!("Failed to remove summary {}: {}", name, e))?;
+                removed += 1;
+            }
+        }
+    }
+    Ok(removed)
+}
+
+/// The summary saved with the tenant's last cache write, or `None` if there isn't one.
+#[tauri::command]
+pub(crate) async fn get_cached_summary(tenant_id: String) -> Result<Option<EndpointStats>, String> {
+    let Ok(content) = fs::read_to_string(get_summary_path(tenant_id.trim())) else {
+        return Ok(None);
+    };
+
+    match serde_json::from_str::<CachedSummary>(&content) {
+        Ok(summary) if summary.tenant_id == tenant_id.trim() => Ok(Some(summary.stats)),
+        Ok(_) => Ok(None),
+        Err(e) => {
+            println!("❌ Failed to parse endpoint summary: {}", e);
+            Ok(None)
+        }
+    }
+}
@@ -0,0 +1,326 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::api_host::resolve_endpoints_base_url;
+use crate::rate_limit::rate_limiter;
+use crate::settings::DedupStrategy;
+use crate::stats::compute_endpoint_stats;
+use crate::{
+    describe_api_error, describe_request_error, fetch_endpoint_pages, fetch_endpoint_total,
+    fetch_with_saved_credentials, http_client, load_cached_endpoints, require_credentials, SophosError,
+};
+
+const LICENSES_URL: &str = "https://api.central.sophos.com/licenses/v1/licenses";
+
+/// Licenses change on renewals and upgrades, not minute to minute.
+const TENANT_INFO_CACHE_TTL: Duration = Duration::from_secs(3600);
+
+/// Health counts as Sophos reports them, to reconcile against the locally computed stats.
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct TenantHealth {
+    /// False when the tenant's license doesn't expose health filtering
+    supported: bool,
+    total: Option<u64>,
+    good: Option<u64>,
+    suspicious: Option<u64>,
+    bad: Option<u64>,
+}
+
+/// Fetches server-side endpoint totals per health status. Each count is a one-item
+/// `healthStatus` query, so this costs four requests regardless of tenant size.
+#[tauri::command]
+pub(crate) async fn fetch_tenant_health(access_token: String) -> Result<TenantHealth, String> {
+    let credentials = require_credentials()?;
+    let client = http_client()?;
+    let base_url = resolve_endpoints_base_url(&client, &access_token, &credentials).await?;
+
+    let mut counts = Vec::new();
+    for status in [None, Some("good"), Some("suspicious"), Some("bad")] {
+        let query: Vec<(&str, String)> = status
+            .map(|status| vec![("healthStatus", status.to_string())])
+            .unwrap_or_default();
+
+        match fetch_endpoint_total(&client, &base_url, &access_token, &credentials.tenant_id, &query).await {
+            Ok(count) => counts.push(count),
+            Err(SophosError::Api { status: 400 | 403 | 404, message }) => {
+                println!("⚠️  Tenant health not available: {}", message);
+                return Ok(TenantHealth {
+                    supported: false,
+                    total: None,
+                    good: None,
+                    suspicious: None,
+                    bad: None,
+                });
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    println!(
+        "🩺 Tenant health: {} total, {} good, {} suspicious, {} bad",
+        counts[0], counts[1], counts[2], counts[3]
+    );
+    Ok(TenantHealth {
+        supported: true,
+        total: Some(counts[0]),
+        good: Some(counts[1]),
+        suspicious: Some(counts[2]),
+        bad: Some(counts[3]),
+    })
+}
+
+/// The tenant's endpoint count from a single one-item page, for the dashboard headline
+/// without fetching the inventory. Falls back to counting the cache when Sophos leaves
+/// the total out.
+#[tauri::command]
+pub(crate) async fn get_endpoint_count(access_token: String) -> Result<u64, String> {
+    let credentials = require_credentials()?;
+    let client = http_client()?;
+    let base_url = resolve_endpoints_base_url(&client, &access_token, &credentials).await?;
+
+    match fetch_endpoint_total(&client, &base_url, &access_token, &credentials.tenant_id, &[]).await {
+        Ok(count) => Ok(count),
+        Err(SophosError::Parse(message)) => {
+            println!("⚠️  {}, counting cached endpoints instead", message);
+            let cached = load_cached_endpoints()
+                .map_err(|e| format!("Sophos did not report an endpoint count and the cache is unavailable: {}", e))?;
+            Ok(cached.len() as u64)
+        }
+        Err(e) => Err(e.into()),
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct HealthCountDelta {
+    status: String,
+    sophos: Option<u64>,
+    local: u64,
+    /// `local - sophos`; `None` when Sophos didn't report the count
+    delta: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct HealthDisagreement {
+    id: String,
+    hostname: Option<String>,
+    local_status: String,
+    sophos_status: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct HealthReconciliation {
+    /// False when the tenant's license doesn't expose health filtering
+    supported: bool,
+    counts: Vec<HealthCountDelta>,
+    disagreements: Vec<HealthDisagreement>,
+}
+
+/// Compares Sophos's health totals with the counts parsed locally from the inventory, and
+/// lists endpoints classified differently. Sophos can only be asked which endpoints are
+/// suspicious or bad, so an endpoint counts as a disagreement when either side puts it in
+/// one of those and the other side doesn't agree.
+#[tauri::command]
+pub(crate) async fn reconcile_health(access_token: String) -> Result<HealthReconciliation, String> {
+    let sophos = fetch_tenant_health(access_token.clone()).await?;
+    let endpoints = fetch_with_saved_credentials(access_token.clone()).await?;
+    let local = compute_endpoint_stats(&endpoints);
+
+    let local_count = |status: &str| local.by_health.get(status).copied().unwrap_or(0) as u64;
+    let counts: Vec<HealthCountDelta> = [
+        ("total", sophos.total, endpoints.len() as u64),
+        ("good", sophos.good, local_count("good")),
+        ("suspicious", sophos.suspicious, local_count("suspicious")),
+        ("bad", sophos.bad, local_count("bad")),
+    ]
+    .into_iter()
+    .map(|(status, sophos, local)| HealthCountDelta {
+        status: status.to_string(),
+        sophos,
+        local,
+        delta: sophos.map(|sophos| local as i64 - sophos as i64),
+    })
+    .collect();
+
+    if !sophos.supported {
+        return Ok(HealthReconciliation {
+            supported: false,
+            counts,
+            disagreements: Vec::new(),
+        });
+    }
+
+    let credentials = require_credentials()?;
+    let client = http_client()?;
+    let base_url = resolve_endpoints_base_url(&client, &access_token, &credentials).await?;
+
+    let mut sophos_status: HashMap<String, &str> = HashMap::new();
+    for status in ["suspicious", "bad"] {
+        let query = [("healthStatus", status.to_string())];
+        let flagged = fetch_endpoint_pages(
+            &client,
+            &base_url,
+            &access_token,
+            &credentials.tenant_id,
+            &query,
+            DedupStrategy::ById,
+        )
+        .await?;
+        for endpoint in flagged {
+            sophos_status.insert(endpoint.id, status);
+        }
+    }
+
+    let disagreements: Vec<HealthDisagreement> = endpoints
+        .iter()
+        .filter_map(|endpoint| {
+            let local_status = endpoint.health_status();
+            let flagged = sophos_status.get(&endpoint.id).copied();
+            let agrees = match flagged {
+                Some(status) => local_status == status,
+                None => local_status != "suspicious" && local_status != "bad",
+            };
+            (!agrees).then(|| HealthDisagreement {
+                id: endpoint.id.clone(),
+                hostname: endpoint.hostname.clone(),
+                local_status,
+                sophos_status: flagged.unwrap_or("good or unknown").to_string(),
+            })
+        })
+        .collect();
+
+    println!("🔎 Health reconciliation: {} endpoints classified differently", disagreements.len());
+    Ok(HealthReconciliation {
+        supported: true,
+        counts,
+        disagreements,
+    })
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct ProductEntitlement {
+    code: Option<String>,
+    name: Option<String>,
+    /// `trial`, `term`, `usage`, ...
+    license_type: Option<String>,
+    end_date: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct TenantInfo {
+    tenant_id: String,
+    /// The tenant's license type when all its licenses share one, `mixed` otherwise, and
+    /// `None` when Sophos lists no licenses
+    billing_type: Option<String>,
+    products: Vec<ProductEntitlement>,
+}
+
+struct TenantInfoCache {
+    fetched_at: Instant,
+    info: TenantInfo,
+}
+
+static TENANT_INFO_CACHE: Mutex<Option<TenantInfoCache>> = Mutex::new(None);
+
+fn cached_tenant_info(tenant_id: &str) -> Option<TenantInfo> {
+    let cache = TENANT_INFO_CACHE.lock().ok()?;
+    cache
+        .as_ref()
+        .filter(|cache| cache.info.tenant_id == tenant_id && cache.fetched_at.elapsed() < TENANT_INFO_CACHE_TTL)
+        .map(|cache| cache.info.clone())
+}
+
+fn store_tenant_info(info: &TenantInfo) {
+    if let Ok(mut cache) = TENANT_INFO_CACHE.lock() {
+        *cache = Some(TenantInfoCache {
+            fetched_at: Instant::now(),
+            info: info.clone(),
+        });
+    }
+}
+
+fn text_field(value: &serde_json::Value, key: &str) -> Option<String> {
+    value
+        .get(key)
+        .and_then(|value| value.as_str())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+        .map(str::to_string)
+}
+
+fn product_entitlement(license: &serde_json::Value) -> ProductEntitlement {
+    let product = license.get("product");
+    ProductEntitlement {
+        code: product.and_then(|product| text_field(product, "code")),
+        name: product.and_then(|product| text_field(product, "name")),
+        license_type: text_field(license, "type"),
+        end_date: text_field(license, "endDate"),
+    }
+}
+
+/// Billing type and product entitlements for the saved tenant, from the licenses API.
+/// Cached in memory for an hour. Fields Sophos omits come back as `None` rather than
+/// failing the request.
+#[tauri::command]
+pub(crate) async fn fetch_tenant_info(access_token: String) -> Result<TenantInfo, String> {
+    let credentials = require_credentials()?;
+    if let Some(info) = cached_tenant_info(&credentials.tenant_id) {
+        return Ok(info);
+    }
+
+    let client = http_client()?;
+    rate_limiter().acquire().await;
+    let response = client
+        .get(LICENSES_URL)
+        .header("Authorization", format!("Bearer {}", &access_token))
+        .header("X-Tenant-ID", &credentials.tenant_id)
+        .header("Accept", "application/json")
+        .send()
+        .await
+        .map_err(|e| format!("License request failed: {}", describe_request_error(&e)))?;
+
+    if !response.status().is_success() {
+        let status = response.status();
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(format!("License request failed ({}): {}", status, describe_api_error(&error_text)));
+    }
+
+    let body: serde_json::Value = response
+        .json()
+        .await
+        .map_err(|e| format!("Failed to parse license response: {}", e))?;
+
+    let products: Vec<ProductEntitlement> = body
+        .get("licenses")
+        .or_else(|| body.get("items"))
+        .and_then(|licenses| licenses.as_array())
+        .map(|licenses| licenses.iter().map(product_entitlement).collect())
+        .unwrap_or_default();
+
+    let mut license_types: Vec<&str> = products
+        .iter()
+        .filter_map(|product| product.license_type.as_deref())
+        .collect();
+    license_types.sort();
+    license_types.dedup();
+    let billing_type = match license_types.as_slice() {
+        [] => None,
+        [only] => Some(only.to_string()),
+        _ => Some("mixed".to_string()),
+    };
+
+    let info = TenantInfo {
+        tenant_id: credentials.tenant_id,
+        billing_type,
+        products,
+    };
+    println!(
+        "🧾 Tenant license: {} ({} products)",
+        info.billing_type.as_deref().unwrap_or("unknown"),
+        info.products.len()
+    );
+    store_tenant_info(&info);
+
+    Ok(info)
+}
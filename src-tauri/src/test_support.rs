@@ -0,0 +1,283 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+
+use crate::client::{RawResponse, SophosClient};
+
+thread_local! {
+    static DATA_DIR: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// The data dir set by `isolated_data_dir` on this thread, if any.
+pub(crate) fn data_dir() -> Option<PathBuf> {
+    DATA_DIR.with(|dir| dir.borrow().clone())
+}
+
+/// Points `get_app_data_dir` at a fresh temp dir for the current thread until the guard
+/// drops, so tests never read or write the real app data. Async tests must stay on the
+/// default current-thread runtime for this to follow spawned tasks.
+pub(crate) struct DataDirGuard {
+    _dir: tempfile::TempDir,
+}
+
+impl Drop for DataDirGuard {
+    fn drop(&mut self) {
+        DATA_DIR.with(|dir| *dir.borrow_mut() = None);
+    }
+}
+
+pub(crate) fn isolated_data_dir() -> DataDirGuard {
+    let dir = tempfile::tempdir().expect("create temp data dir");
+    DATA_DIR.with(|current| *current.borrow_mut() = Some(dir.path().to_path_buf()));
+    DataDirGuard { _dir: dir }
+}
+
+/// A plain HTTP/1.1 server on localhost answering one connection per canned response,
+/// in order. The handle resolves to the raw requests it received (head and body).
+pub(crate) async fn mock_server(responses: Vec<(u16, String)>) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.expect("bind mock server");
+    let url = format!("http://{}", listener.local_addr().expect("mock server address"));
+
+    let handle = tokio::spawn(async move {
+        let mut requests = Vec::new();
+        for (status, body) in responses {
+            let (mut stream, _) = listener.accept().await.expect("accept");
+
+            let mut raw = Vec::new();
+            let mut buffer = [0u8; 4096];
+            loop {
+                let read = stream.read(&mut buffer).await.expect("read request");
+                raw.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&raw).to_string();
+                if let Some(head_end) = text.find("\r\n\r\n") {
+                    let content_length = text[..head_end]
+                        .lines()
+                        .find_map(|line| line.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().to_string()))
+                        .and_then(|v| v.parse::<usize>().ok())
+                        .unwrap_or(0);
+                    if raw.len() >= head_end + 4 + content_length {
+                        break;
+                    }
+                }
+                if read == 0 {
+                    break;
+                }
+            }
+            requests.push(String::from_utf8_lossy(&raw).to_string());
+
+            let response = format!(
+                "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.expect("write response");
+            stream.shutdown().await.ok();
+        }
+        requests
+    });
+
+    (url, handle)
+}
+
+/// Value of `name` in a raw request captured by `mock_server`, matched case-insensitively.
+pub(crate) fn request_header(request: &str, name: &str) -> Option<String> {
+    let prefix = format!("{}:", name.to_lowercase());
+    request
+        .lines()
+        .take_while(|line| !line.is_empty())
+        .find(|line| line.to_lowercase().starts_with(&prefix))
+        .map(|line| line[prefix.len()..].trim().to_string())
+}
+
+thread_local! {
+    static TOKEN_STATE: &'static crate::token::TokenState = Box::leak(Box::default());
+    static CLIENT_STATE: &'static crate::client::ClientState = Box::leak(Box::default());
+}
+
+/// Stand-in for the app's managed `TokenState`, one per test thread.
+pub(crate) fn token_state() -> &'static crate::token::TokenState {
+    TOKEN_STATE.with(|state| *state)
+}
+
+/// Stand-in for the app's managed `ClientState`, one per test thread.
+pub(crate) fn client_state() -> &'static crate::client::ClientState {
+    CLIENT_STATE.with(|state| *state)
+}
+
+/// Saves credentials as the active profile in the current (isolated) data dir.
+pub(crate) fn save_credentials(client_id: &str, tenant_id: &str) {
+    let mut secrets = crate::SecretsFile::default();
+    secrets.profiles.insert(
+        crate::DEFAULT_PROFILE.to_string(),
+        crate::SophosCredentials {
+            client_id: client_id.to_string(),
+            client_secret: "secret".to_string(),
+            tenant_id: tenant_id.to_string(),
+            region: "eu01".to_string(),
+            allow_unknown_region: false,
+            credentials_created_at: None,
+        },
+    );
+    crate::write_secrets_file(&secrets).expect("save test credentials");
+}
+
+/// One canned endpoint record.
+pub(crate) fn endpoint_json(id: &str, hostname: &str, last_seen: &str) -> serde_json::Value {
+    serde_json::json!({ "id": id, "hostname": hostname, "lastSeen": last_seen })
+}
+
+/// A successful endpoint page; `next_key` continues key pagination.
+pub(crate) fn page(items: Vec<serde_json::Value>, next_key: Option<&str>) -> Result<RawResponse, String> {
+    let mut pages = serde_json::json!({});
+    if let Some(next_key) = next_key {
+        pages["nextKey"] = serde_json::json!(next_key);
+    }
+    Ok(RawResponse {
+        status: 200,
+        body: serde_json::json!({ "items": items, "pages": pages }).to_string(),
+    })
+}
+
+/// A successful response carrying `body` as JSON.
+pub(crate) fn json_response(body: serde_json::Value) -> Result<RawResponse, String> {
+    Ok(RawResponse {
+        status: 200,
+        body: body.to_string(),
+    })
+}
+
+/// An API error response with a Sophos-style error body.
+pub(crate) fn status(status: u16) -> Result<RawResponse, String> {
+    Ok(RawResponse {
+        status,
+        body: serde_json::json!({ "error": "mock", "message": format!("mock status {}", status) }).to_string(),
+    })
+}
+
+/// A page request as the fetch logic sent it.
+#[derive(Debug, Clone)]
+pub(crate) struct PageRequest {
+    pub(crate) access_token: String,
+    pub(crate) query: Vec<(String, String)>,
+}
+
+impl PageRequest {
+    pub(crate) fn param(&self, name: &str) -> Option<&str> {
+        self.query.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+/// A call made through one of `FakeClient`'s non-paging methods.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ApiCall {
+    pub(crate) method: &'static str,
+    pub(crate) url: String,
+    pub(crate) body: Option<serde_json::Value>,
+}
+
+/// In-memory `SophosClient`: token exchanges are counted and numbered (`token-1`, ...),
+/// endpoint pages are served from a queue of canned responses, in order, and every other
+/// call (whoami, details, actions) from a second queue. Each exchange yields to the
+/// runtime first, so concurrent callers get a chance to pile up behind it.
+#[derive(Default)]
+pub(crate) struct FakeClient {
+    pub(crate) token_exchanges: AtomicUsize,
+    pages: Mutex<VecDeque<Result<RawResponse, String>>>,
+    requests: Mutex<Vec<PageRequest>>,
+    responses: Mutex<VecDeque<Result<RawResponse, String>>>,
+    calls: Mutex<Vec<ApiCall>>,
+}
+
+impl FakeClient {
+    pub(crate) fn with_pages(pages: Vec<Result<RawResponse, String>>) -> Self {
+        FakeClient {
+            pages: Mutex::new(pages.into()),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn with_responses(responses: Vec<Result<RawResponse, String>>) -> Self {
+        FakeClient {
+            responses: Mutex::new(responses.into()),
+            ..Default::default()
+        }
+    }
+
+    pub(crate) fn requests(&self) -> Vec<PageRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    pub(crate) fn calls(&self) -> Vec<ApiCall> {
+        self.calls.lock().unwrap().clone()
+    }
+
+    fn respond(&self, method: &'static str, url: &str, body: Option<&serde_json::Value>) -> Result<RawResponse, String> {
+        self.calls.lock().unwrap().push(ApiCall {
+            method,
+            url: url.to_string(),
+            body: body.cloned(),
+        });
+        self.responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| Err("FakeClient has no more canned responses".to_string()))
+    }
+}
+
+impl SophosClient for FakeClient {
+    async fn get_token(&self, _client_id: &str, _client_secret: &str) -> Result<crate::SophosTokenResponse, String> {
+        let exchange = self.token_exchanges.fetch_add(1, Ordering::SeqCst) + 1;
+        tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        Ok(crate::SophosTokenResponse {
+            access_token: format!("token-{}", exchange),
+            token_type: "bearer".to_string(),
+            expires_in: 3600,
+        })
+    }
+
+    async fn get_endpoints_page(
+        &self,
+        _base_url: &str,
+        access_token: &str,
+        _tenant_id: &str,
+        query: &[(&str, String)],
+    ) -> Result<RawResponse, String> {
+        self.requests.lock().unwrap().push(PageRequest {
+            access_token: access_token.to_string(),
+            query: query.iter().map(|(key, value)| (key.to_string(), value.clone())).collect(),
+        });
+        self.pages
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| Err("FakeClient has no more canned pages".to_string()))
+    }
+
+    async fn get_whoami(&self, _access_token: &str) -> Result<RawResponse, String> {
+        self.respond("GET", crate::WHOAMI_URL, None)
+    }
+
+    async fn get_resource(&self, url: &str, _access_token: &str, _tenant_id: &str) -> Result<RawResponse, String> {
+        self.respond("GET", url, None)
+    }
+
+    async fn post_action(
+        &self,
+        url: &str,
+        _access_token: &str,
+        _tenant_id: &str,
+        body: &serde_json::Value,
+    ) -> Result<RawResponse, String> {
+        self.respond("POST", url, Some(body))
+    }
+
+    async fn delete_resource(&self, url: &str, _access_token: &str, _tenant_id: &str) -> Result<RawResponse, String> {
+        self.respond("DELETE", url, None)
+    }
+}
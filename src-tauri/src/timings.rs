@@ -0,0 +1,147 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+static LAST_FETCH_TIMINGS: Mutex<Option<FetchTimings>> = Mutex::new(None);
+static LAST_PAGES_METADATA: Mutex<Option<PagesMetadata>> = Mutex::new(None);
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct PageTiming {
+    page: u32,
+    request_ms: u64,
+    parse_ms: u64,
+    endpoints: usize,
+    /// Items that failed to parse and were left out
+    skipped: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct FetchTimings {
+    total_pages: u32,
+    total_duration_ms: u64,
+    slowest_page: Option<PageTiming>,
+    average_page_latency_ms: u64,
+    skipped_items: usize,
+    pages: Vec<PageTiming>,
+}
+
+impl FetchTimings {
+    pub(crate) fn average_page_latency_ms(&self) -> u64 {
+        self.average_page_latency_ms
+    }
+}
+
+/// Collects per-page timings during a paginated fetch. Recording is a couple of
+/// `Instant` reads per page, so it's always on.
+pub(crate) struct FetchTimer {
+    started: Instant,
+    pages: Vec<PageTiming>,
+}
+
+impl FetchTimer {
+    pub(crate) fn start() -> Self {
+        FetchTimer {
+            started: Instant::now(),
+            pages: Vec::new(),
+        }
+    }
+
+    pub(crate) fn record_page(
+        &mut self,
+        page: u32,
+        request: Duration,
+        parse: Duration,
+        endpoints: usize,
+        skipped: usize,
+    ) {
+        self.pages.push(PageTiming {
+            page,
+            request_ms: request.as_millis() as u64,
+            parse_ms: parse.as_millis() as u64,
+            endpoints,
+            skipped,
+        });
+    }
+
+    /// Stores the summary as the last completed fetch.
+    pub(crate) fn finish(self) {
+        let total_pages = self.pages.len() as u32;
+        let average_page_latency_ms = if self.pages.is_empty() {
+            0
+        } else {
+            self.pages.iter().map(|page| page.request_ms).sum::<u64>() / self.pages.len() as u64
+        };
+        let slowest_page = self.pages.iter().max_by_key(|page| page.request_ms).cloned();
+
+        let timings = FetchTimings {
+            total_pages,
+            total_duration_ms: self.started.elapsed().as_millis() as u64,
+            slowest_page,
+            average_page_latency_ms,
+            skipped_items: self.pages.iter().map(|page| page.skipped).sum(),
+            pages: self.pages,
+        };
+
+        println!(
+            "⏱️  Fetch took {}ms across {} pages (avg page latency {}ms)",
+            timings.total_duration_ms, timings.total_pages, timings.average_page_latency_ms
+        );
+
+        if let Ok(mut last) = LAST_FETCH_TIMINGS.lock() {
+            *last = Some(timings);
+        }
+    }
+}
+
+/// What Sophos said about the size of the last paginated fetch, next to what it returned.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct PagesMetadata {
+    /// The `pages` object of the last page, as Sophos sent it
+    last_pages: Option<serde_json::Value>,
+    /// `pages.items` from the first page: the tenant's total endpoint count
+    reported_total: Option<u64>,
+    fetched: usize,
+    skipped: usize,
+    /// True when fewer endpoints came back than Sophos reported
+    incomplete: bool,
+}
+
+pub(crate) fn record_pages_metadata(
+    last_pages: Option<serde_json::Value>,
+    reported_total: Option<u64>,
+    fetched: usize,
+    skipped: usize,
+) {
+    let incomplete = reported_total.is_some_and(|total| ((fetched + skipped) as u64) < total);
+    if incomplete {
+        println!(
+            "   ⚠️  Sophos reported {} endpoints but only {} were fetched",
+            reported_total.unwrap_or_default(),
+            fetched + skipped
+        );
+    }
+
+    if let Ok(mut last) = LAST_PAGES_METADATA.lock() {
+        *last = Some(PagesMetadata {
+            last_pages,
+            reported_total,
+            fetched,
+            skipped,
+            incomplete,
+        });
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn get_last_pages_metadata() -> Result<Option<PagesMetadata>, String> {
+    Ok(LAST_PAGES_METADATA.lock().ok().and_then(|last| last.clone()))
+}
+
+pub(crate) fn last_fetch_timings() -> Option<FetchTimings> {
+    LAST_FETCH_TIMINGS.lock().ok().and_then(|last| last.clone())
+}
+
+#[tauri::command]
+pub(crate) async fn get_last_fetch_timings() -> Result<Option<FetchTimings>, String> {
+    Ok(last_fetch_timings())
+}
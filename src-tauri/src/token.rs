@@ -0,0 +1,253 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::client::SophosClient;
+use crate::settings::load_settings;
+use crate::{get_app_data_dir, http_client};
+
+pub(crate) const TOKEN_FILE: &str = "sophos_token.json";
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub(crate) struct CachedToken {
+    access_token: String,
+    /// Client the token was issued to, so switching credentials never reuses it
+    client_id: String,
+    expires_in: u64,
+    /// Unix timestamp (seconds)
+    expires_at: u64,
+}
+
+impl CachedToken {
+    /// The configured expiry margin, capped at half the token's lifetime. A margin at or
+    /// beyond `expires_in` would make every fresh token look expired and force a new
+    /// exchange on each request.
+    fn expiry_margin(&self) -> u64 {
+        load_settings().token_expiry_margin_seconds.min(self.expires_in / 2)
+    }
+
+    fn is_valid_for(&self, client_id: &str) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        self.client_id == client_id && now + self.expiry_margin() < self.expires_at
+    }
+}
+
+/// Managed state holding the current access token. The async mutex is held across the
+/// token exchange, so concurrent callers wait for one in-flight request and reuse its
+/// result instead of each exchanging credentials.
+#[derive(Default)]
+pub(crate) struct TokenState {
+    token: tokio::sync::Mutex<Option<CachedToken>>,
+}
+
+impl TokenState {
+    /// Forgets the in-memory token so the next request exchanges credentials again.
+    pub(crate) async fn clear(&self) {
+        *self.token.lock().await = None;
+    }
+
+    pub(crate) async fn get_or_refresh(&self, client_id: &str, client_secret: &str) -> Result<String, String> {
+        self.get_or_refresh_with(&http_client()?, client_id, client_secret).await
+    }
+
+    async fn get_or_refresh_with(
+        &self,
+        client: &impl SophosClient,
+        client_id: &str,
+        client_secret: &str,
+    ) -> Result<String, String> {
+        let mut cached = self.token.lock().await;
+
+        // Cold start: pick up a token persisted by a previous session
+        if cached.is_none() {
+            *cached = load_token_file();
+        }
+
+        if let Some(token) = cached.as_ref().filter(|token| token.is_valid_for(client_id)) {
+            return Ok(token.access_token.clone());
+        }
+
+        exchange_into(client, &mut cached, client_id, client_secret).await
+    }
+
+    /// Replaces a token the API rejected before its recorded expiry. If another caller has
+    /// already swapped it out, that newer token is returned instead of exchanging again.
+    /// `client` is the one the rejected request went through.
+    pub(crate) async fn refresh_rejected(
+        &self,
+        client: &impl SophosClient,
+        rejected_token: &str,
+        client_id: &str,
+        client_secret: &str,
+    ) -> Result<String, String> {
+        let mut cached = self.token.lock().await;
+
+        if let Some(token) = cached
+            .as_ref()
+            .filter(|token| token.access_token != rejected_token && token.is_valid_for(client_id))
+        {
+            return Ok(token.access_token.clone());
+        }
+
+        exchange_into(client, &mut cached, client_id, client_secret).await
+    }
+}
+
+/// Exchanges credentials for a new token through `client` and stores it in `cached` and
+/// on disk.
+async fn exchange_into(
+    client: &impl SophosClient,
+    cached: &mut Option<CachedToken>,
+    client_id: &str,
+    client_secret: &str,
+) -> Result<String, String> {
+    let token_response = client.get_token(client_id, client_secret).await?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap()
+        .as_secs();
+    let token = CachedToken {
+        access_token: token_response.access_token,
+        client_id: client_id.to_string(),
+        expires_in: token_response.expires_in,
+        expires_at: now + token_response.expires_in,
+    };
+
+    save_token_file(&token);
+    *cached = Some(token.clone());
+
+    Ok(token.access_token)
+}
+
+fn get_token_path() -> std::path::PathBuf {
+    get_app_data_dir().join(TOKEN_FILE)
+}
+
+fn load_token_file() -> Option<CachedToken> {
+    if !load_settings().cache_enabled {
+        return None;
+    }
+
+    let content = fs::read_to_string(get_token_path()).ok()?;
+    match serde_json::from_str::<CachedToken>(&content) {
+        Ok(token) => Some(token),
+        Err(e) => {
+            println!("❌ Failed to parse cached token: {}", e);
+            None
+        }
+    }
+}
+
+/// The client id the persisted token was issued to, if one is cached.
+pub(crate) fn cached_token_client_id() -> Option<String> {
+    load_token_file().map(|token| token.client_id)
+}
+
+fn save_token_file(token: &CachedToken) {
+    if !load_settings().cache_enabled {
+        return;
+    }
+
+    match serde_json::to_string_pretty(token) {
+        Ok(json_content) => {
+            if let Err(e) = fs::write(get_token_path(), json_content) {
+                println!("❌ Failed to save token cache: {}", e);
+            }
+        }
+        Err(e) => println!("❌ Failed to serialize token cache: {}", e),
+    }
+}
+
+/// Drops the cached token, in memory and on disk, so the next request re-authenticates.
+/// Use after rotating the client secret in Sophos Central.
+#[tauri::command]
+pub(crate) async fn invalidate_token(token_state: tauri::State<'_, TokenState>) -> Result<String, String> {
+    token_state.clear().await;
+
+    let token_path = get_token_path();
+    if !token_path.exists() {
+        return Ok("No cached token to invalidate".to_string());
+    }
+
+    fs::remove_file(&token_path).map_err(|e| format!("Failed to remove cached token: {}", e))?;
+    println!("🔑 Cached token invalidated");
+    Ok("Cached token invalidated".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{isolated_data_dir, FakeClient};
+    use std::sync::atomic::Ordering;
+    use std::sync::Arc;
+
+    #[tokio::test]
+    async fn concurrent_callers_share_one_exchange() {
+        let _data_dir = isolated_data_dir();
+        let state = Arc::new(TokenState::default());
+        let client = Arc::new(FakeClient::default());
+
+        let callers: Vec<_> = (0..8)
+            .map(|_| {
+                let state = state.clone();
+                let client = client.clone();
+                tokio::spawn(async move { state.get_or_refresh_with(&*client, "client", "secret").await })
+            })
+            .collect();
+
+        let mut tokens = Vec::new();
+        for caller in callers {
+            tokens.push(caller.await.unwrap().unwrap());
+        }
+
+        assert_eq!(client.token_exchanges.load(Ordering::SeqCst), 1);
+        assert!(tokens.iter().all(|token| token == "token-1"), "{:?}", tokens);
+    }
+
+    #[tokio::test]
+    async fn concurrent_rejections_replace_the_token_once() {
+        let _data_dir = isolated_data_dir();
+        let state = Arc::new(TokenState::default());
+        let client = Arc::new(FakeClient::default());
+        let rejected = state.get_or_refresh_with(&*client, "client", "secret").await.unwrap();
+
+        let callers: Vec<_> = (0..8)
+            .map(|_| {
+                let state = state.clone();
+                let client = client.clone();
+                let rejected = rejected.clone();
+                tokio::spawn(async move {
+                    state
+                        .refresh_rejected(&*client, &rejected, "client", "secret")
+                        .await
+                })
+            })
+            .collect();
+
+        let mut tokens = Vec::new();
+        for caller in callers {
+            tokens.push(caller.await.unwrap().unwrap());
+        }
+
+        assert_eq!(client.token_exchanges.load(Ordering::SeqCst), 2);
+        assert!(tokens.iter().all(|token| token == "token-2"), "{:?}", tokens);
+    }
+
+    #[tokio::test]
+    async fn switching_client_id_exchanges_again() {
+        let _data_dir = isolated_data_dir();
+        let state = TokenState::default();
+        let client = FakeClient::default();
+
+        let first = state.get_or_refresh_with(&client, "client-a", "secret").await.unwrap();
+        let again = state.get_or_refresh_with(&client, "client-a", "secret").await.unwrap();
+        let switched = state.get_or_refresh_with(&client, "client-b", "secret").await.unwrap();
+
+        assert_eq!(first, again);
+        assert_ne!(first, switched);
+        assert_eq!(client.token_exchanges.load(Ordering::SeqCst), 2);
+    }
+}
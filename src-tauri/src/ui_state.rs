@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::get_app_data_dir;
+
+pub(crate) const UI_STATE_FILE: &str = "ui_state.json";
+
+/// Non-secret UI selections remembered across restarts. Kept out of the secrets file
+/// so nothing sensitive can end up here.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub(crate) struct LastSelection {
+    last_tenant_id: Option<String>,
+    last_region: Option<String>,
+}
+
+fn get_ui_state_path() -> std::path::PathBuf {
+    get_app_data_dir().join(UI_STATE_FILE)
+}
+
+#[tauri::command]
+pub(crate) async fn get_last_selection() -> Result<LastSelection, String> {
+    let Ok(content) = fs::read_to_string(get_ui_state_path()) else {
+        return Ok(LastSelection::default());
+    };
+
+    match serde_json::from_str::<LastSelection>(&content) {
+        Ok(selection) => Ok(selection),
+        Err(e) => {
+            println!("❌ Failed to parse UI state, using defaults: {}", e);
+            Ok(LastSelection::default())
+        }
+    }
+}
+
+#[tauri::command]
+pub(crate) async fn set_last_selection(
+    last_tenant_id: Option<String>,
+    last_region: Option<String>,
+) -> Result<LastSelection, String> {
+    let selection = LastSelection {
+        last_tenant_id,
+        last_region,
+    };
+
+    let json_content = serde_json::to_string_pretty(&selection)
+        .map_err(|e| format!("Failed to serialize UI state: {}", e))?;
+    fs::write(get_ui_state_path(), json_content).map_err(|e| format!("Failed to save UI state: {}", e))?;
+
+    Ok(selection)
+}
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::api_host::resolve_endpoints_base_url;
+use crate::details::fetch_details_bulk;
+use crate::{fetch_with_saved_credentials, http_client, require_credentials};
+
+/// Detail requests in flight at once; the shared rate limiter still applies.
+const UPDATE_CHECK_CONCURRENCY: usize = 5;
+
+/// An agent whose last successful update is older than this counts as out of date,
+/// whatever `updateStatus` says.
+const OUTDATED_AFTER_DAYS: i64 = 7;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub(crate) struct OutdatedAgent {
+    id: String,
+    hostname: Option<String>,
+    /// `updateStatus` as Sophos reported it
+    update_status: Option<String>,
+    /// Last successful update, or the last update check when that's all Sophos reports
+    last_update: Option<String>,
+    days_since_update: Option<i64>,
+}
+
+fn detail_text<'a>(detail: &'a serde_json::Value, key: &str) -> Option<&'a str> {
+    detail
+        .get(key)
+        .and_then(|value| value.as_str())
+        .map(str::trim)
+        .filter(|text| !text.is_empty())
+}
+
+/// The agent's update state from its detail record, or `None` when it's up to date or
+/// the record carries no update information at all.
+fn outdated_agent(
+    id: String,
+    hostname: Option<String>,
+    detail: &serde_json::Value,
+    now: chrono::DateTime<chrono::Utc>,
+) -> Option<OutdatedAgent> {
+    let update_status = detail_text(detail, "updateStatus").map(str::to_string);
+    let last_update = detail_text(detail, "lastSuccessfulUpdate")
+        .or_else(|| detail_text(detail, "lastUpdateCheck"))
+        .map(str::to_string);
+    let days_since_update = last_update
+        .as_deref()
+        .and_then(|last_update| chrono::DateTime::parse_from_rfc3339(last_update).ok())
+        .map(|last_update| now.signed_duration_since(last_update.with_timezone(&chrono::Utc)).num_days());
+
+    let status_outdated = update_status
+        .as_deref()
+        .is_some_and(|status| !status.eq_ignore_ascii_case("upToDate"));
+    let age_outdated = days_since_update.is_some_and(|days| days > OUTDATED_AFTER_DAYS);
+    if !status_outdated && !age_outdated {
+        return None;
+    }
+
+    Some(OutdatedAgent {
+        id,
+        hostname,
+        update_status,
+        last_update,
+        days_since_update,
+    })
+}
+
+/// Endpoints whose agent reports an update status other than `upToDate`, or hasn't
+/// updated in over `OUTDATED_AFTER_DAYS` days, longest without an update first. Reads
+/// each endpoint's detail record; endpoints whose detail can't be fetched are skipped.
+#[tauri::command]
+pub(crate) async fn find_outdated_agents(access_token: String) -> Result<Vec<OutdatedAgent>, String> {
+    let credentials = require_credentials()?;
+    let endpoints = fetch_with_saved_credentials(access_token.clone()).await?;
+    let client = http_client()?;
+    let base_url = resolve_endpoints_base_url(&client, &access_token, &credentials).await?;
+
+    let hostnames: HashMap<String, Option<String>> = endpoints
+        .into_iter()
+        .map(|endpoint| (endpoint.id, endpoint.hostname))
+        .collect();
+    let endpoint_ids: Vec<String> = hostnames.keys().cloned().collect();
+
+    let results = fetch_details_bulk(
+        &client,
+        &base_url,
+        &access_token,
+        &credentials.tenant_id,
+        endpoint_ids,
+        UPDATE_CHECK_CONCURRENCY,
+    )
+    .await;
+
+    let now = chrono::Utc::now();
+    let mut outdated = Vec::new();
+    for (endpoint_id, detail) in results {
+        let detail = match detail {
+            Ok(detail) => detail,
+            Err(e) => {
+                println!("   ⚠️  Skipping endpoint {}: {}", endpoint_id, e);
+                continue;
+            }
+        };
+        let hostname = hostnames.get(&endpoint_id).cloned().flatten();
+        if let Some(agent) = outdated_agent(endpoint_id, hostname, &detail, now) {
+            outdated.push(agent);
+        }
+    }
+
+    // Unknown age sorts last
+    outdated.sort_by_key(|agent| std::cmp::Reverse(agent.days_since_update.unwrap_or(i64::MIN)));
+
+    println!("📦 {} agents are out of date", outdated.len());
+    Ok(outdated)
+}
@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+
+use crate::export::write_endpoints_csv;
+use crate::{get_app_data_dir, load_cached_endpoints, SophosEndpoint};
+
+pub(crate) const VIEWS_FILE: &str = "saved_views.json";
+
+/// A named filter over the cached inventory. Every field is optional; an unset field
+/// matches everything. Text comparisons ignore case.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+#[serde(default)]
+pub(crate) struct SavedView {
+    /// `os.platform`, e.g. `windows`
+    os_platform: Option<String>,
+    /// Endpoint type, e.g. `server`
+    endpoint_type: Option<String>,
+    online: Option<bool>,
+    /// Overall health: `good`, `suspicious`, `bad` or `unknown`
+    health: Option<String>,
+    hostname_contains: Option<String>,
+}
+
+impl SavedView {
+    fn matches(&self, endpoint: &SophosEndpoint) -> bool {
+        let os_platform = endpoint
+            .os
+            .as_ref()
+            .and_then(|os| os.get("platform"))
+            .and_then(|value| value.as_str())
+            .unwrap_or_default();
+        let endpoint_type = endpoint
+            .endpoint_type
+            .as_ref()
+            .map_or("unknown", |endpoint_type| endpoint_type.as_str());
+
+        self.os_platform
+            .as_ref()
+            .is_none_or(|wanted| wanted.trim().eq_ignore_ascii_case(os_platform))
+            && self
+                .endpoint_type
+                .as_ref()
+                .is_none_or(|wanted| wanted.trim().eq_ignore_ascii_case(endpoint_type))
+            && self.online.is_none_or(|wanted| endpoint.online.unwrap_or(false) == wanted)
+            && self
+                .health
+                .as_ref()
+                .is_none_or(|wanted| wanted.trim().eq_ignore_ascii_case(&endpoint.health_status()))
+            && self.hostname_contains.as_ref().is_none_or(|needle| {
+                endpoint
+                    .hostname
+                    .as_ref()
+                    .is_some_and(|hostname| hostname.to_lowercase().contains(&needle.trim().to_lowercase()))
+            })
+    }
+}
+
+fn get_views_path() -> std::path::PathBuf {
+    get_app_data_dir().join(VIEWS_FILE)
+}
+
+/// Reads every saved view. A view that doesn't parse is skipped on its own rather than
+/// hiding the rest.
+fn load_views() -> BTreeMap<String, SavedView> {
+    let Ok(content) = fs::read_to_string(get_views_path()) else {
+        return BTreeMap::new();
+    };
+
+    let raw = match serde_json::from_str::<BTreeMap<String, serde_json::Value>>(&content) {
+        Ok(raw) => raw,
+        Err(e) => {
+            println!("❌ Failed to parse saved views: {}", e);
+            return BTreeMap::new();
+        }
+    };
+
+    raw.into_iter()
+        .filter_map(|(name, value)| match serde_json::from_value::<SavedView>(value) {
+            Ok(view) => Some((name, view)),
+            Err(e) => {
+                println!("⚠️  Skipping saved view '{}': {}", name, e);
+                None
+            }
+        })
+        .collect()
+}
+
+fn save_views(views: &BTreeMap<String, SavedView>) -> Result<(), String> {
+    let json_content =
+        serde_json::to_string_pretty(views).map_err(|e| format!("Failed to serialize saved views: {}", e))?;
+    fs::write(get_views_path(), json_content).map_err(|e| format!("Failed to save views: {}", e))
+}
+
+#[tauri::command]
+pub(crate) async fn save_view(name: String, view: SavedView) -> Result<SavedView, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("View name cannot be empty".to_string());
+    }
+
+    let mut views = load_views();
+    views.insert(name.clone(), view.clone());
+    save_views(&views)?;
+
+    println!("💾 Saved view '{}'", name);
+    Ok(view)
+}
+
+#[tauri::command]
+pub(crate) async fn list_views() -> Result<BTreeMap<String, SavedView>, String> {
+    Ok(load_views())
+}
+
+/// Returns whether a view by that name existed.
+#[tauri::command]
+pub(crate) async fn delete_view(name: String) -> Result<bool, String> {
+    let mut views = load_views();
+    if views.remove(name.trim()).is_none() {
+        return Ok(false);
+    }
+
+    save_views(&views)?;
+    println!("🗑️  Deleted view '{}'", name.trim());
+    Ok(true)
+}
+
+pub(crate) fn view_endpoints(name: &str) -> Result<Vec<SophosEndpoint>, String> {
+    let view = load_views()
+        .remove(name.trim())
+        .ok_or_else(|| format!("No saved view named '{}'", name.trim()))?;
+
+    Ok(load_cached_endpoints()?
+        .into_iter()
+        .filter(|endpoint| view.matches(endpoint))
+        .collect())
+}
+
+/// Cached endpoints matching the named view.
+#[tauri::command]
+pub(crate) async fn apply_view(name: String) -> Result<Vec<SophosEndpoint>, String> {
+    view_endpoints(&name)
+}
+
+/// Writes the endpoints matching the named view to `path` as CSV.
+#[tauri::command]
+pub(crate) async fn export_view_csv(view_name: String, path: String) -> Result<String, String> {
+    let endpoints = view_endpoints(&view_name)?;
+    let rows = write_endpoints_csv(&endpoints, &path)?;
+
+    println!("📄 Exported {} endpoints from view '{}' to {}", rows, view_name.trim(), path);
+    Ok(format!("Exported {} endpoints to {}", rows, path))
+}